@@ -0,0 +1,140 @@
+//! Capture the search result pages of a club, as fixtures for the parser tests.
+//!
+//! The names, descriptions, and additional columns of the bookings get replaced. The anonymized
+//! pages are parsed again, and only written if none of the original values is left.
+
+use anyhow::{anyhow, bail};
+use chrono::{Duration, Utc};
+use elkato_api::{
+    cors::CorsProxy,
+    record::{Cassette, Recorder},
+    Api, Booking, BookingState, Credentials, ListOptions, DEFAULT_TIMEZONE,
+};
+use futures::stream::{StreamExt, TryStreamExt};
+use std::{collections::BTreeSet, path::PathBuf};
+use url::Url;
+
+fn options() -> ListOptions {
+    let today = Utc::now().date_naive();
+    ListOptions {
+        start_from: Some(today - Duration::days(14)),
+        end_to: Some(today + Duration::days(14)),
+        state: BookingState::All,
+        ..Default::default()
+    }
+}
+
+async fn list(api: &Api) -> anyhow::Result<Vec<Booking>> {
+    Ok(api
+        .list_bookings(options())
+        .boxed_local()
+        .try_collect()
+        .await?)
+}
+
+/// The values of a booking which might identify a person.
+fn personal(booking: &Booking) -> impl Iterator<Item = &str> {
+    [&booking.owner, &booking.booker, &booking.description]
+        .into_iter()
+        .chain(booking.columns.iter().map(|(_, value)| value))
+        .map(String::as_str)
+        .filter(|value| !value.is_empty())
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Replace the cell contents, which are the only place the parser takes the values from.
+fn anonymize(body: &str, club: &str, replacements: &[(String, String)]) -> String {
+    let mut body = body.replace(&format!("club={club}"), "club=demo");
+    for (value, replacement) in replacements {
+        for value in [value.clone(), escape(value)] {
+            body = body.replace(&format!(">{value}<"), &format!(">{replacement}<"));
+        }
+    }
+    body
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env_logger::init();
+
+    let dir = PathBuf::from(
+        std::env::args()
+            .nth(1)
+            .ok_or_else(|| anyhow!("Usage: capture <directory>"))?,
+    );
+
+    let url = match std::env::var("ELKATO_URL") {
+        Ok(url) => Url::parse(&url)?,
+        Err(_) => Url::parse("https://www.elkato.de/buchung/")?,
+    };
+    let credentials = Credentials {
+        username: std::env::var("ELKATO_USERNAME")?,
+        password: std::env::var("ELKATO_PASSWORD")?,
+        club: std::env::var("ELKATO_CLUB")?,
+    };
+    let timezone = match std::env::var("ELKATO_TIMEZONE") {
+        Ok(tz) => tz
+            .parse()
+            .map_err(|err| anyhow!("Invalid timezone: {err}"))?,
+        Err(_) => DEFAULT_TIMEZONE,
+    };
+    let api = || -> anyhow::Result<Api> {
+        Ok(Api::new(url.clone(), CorsProxy::None, credentials.clone())?.with_timezone(timezone))
+    };
+
+    let recorder = Recorder::new();
+    let bookings = list(&api()?.with_recorder(recorder.clone())).await?;
+
+    let mut originals: BTreeSet<&str> = bookings.iter().flat_map(personal).collect();
+    originals.insert(&credentials.username);
+    let replacements: Vec<_> = originals
+        .iter()
+        .enumerate()
+        .map(|(n, value)| (value.to_string(), format!("anonym{}", n + 1)))
+        .collect();
+
+    let mut cassette = recorder.cassette();
+    for interaction in &mut cassette.interactions {
+        interaction.response.body =
+            anonymize(&interaction.response.body, &credentials.club, &replacements);
+    }
+
+    // the anonymized pages must still parse, but without any of the original values
+    let anonymized = list(&api()?.with_replay(cassette.clone())).await?;
+    if anonymized.len() != bookings.len() {
+        bail!(
+            "Anonymized pages have {} instead of {} bookings",
+            anonymized.len(),
+            bookings.len()
+        );
+    }
+    if let Some(value) = anonymized
+        .iter()
+        .flat_map(personal)
+        .find(|value| originals.contains(value))
+    {
+        bail!("Unable to anonymize: {value}");
+    }
+
+    save(&dir, &cassette)
+}
+
+fn save(dir: &std::path::Path, cassette: &Cassette) -> anyhow::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    for (n, interaction) in cassette.interactions.iter().enumerate() {
+        let path = dir.join(format!("captured_{}.html", n + 1));
+        std::fs::write(&path, &interaction.response.body)?;
+        println!("Captured: {}", path.display());
+    }
+    println!(
+        "Check the pages for other personal data, like the name of the club, before adding them"
+    );
+    Ok(())
+}
@@ -1,6 +1,8 @@
 use anyhow::anyhow;
 use chrono::{Duration, Utc};
-use elkato_api::{Api, Booking, Credentials, ListOptions};
+use elkato_api::{
+    cors::CorsProxy, record::Recorder, Api, Booking, Credentials, ListOptions, DEFAULT_TIMEZONE,
+};
 use futures::stream::{StreamExt, TryStreamExt};
 use url::Url;
 
@@ -13,16 +15,31 @@ async fn main() -> anyhow::Result<()> {
             .map_err(|err| anyhow!(err))
             .and_then(|url| Ok(Url::parse(&url)?))
             .or_else(|_| Ok::<_, anyhow::Error>(Url::parse("https://www.elkato.de/buchung/")?))?,
+        CorsProxy::None,
         Credentials {
             username: std::env::var("ELKATO_USERNAME")?,
             password: std::env::var("ELKATO_PASSWORD")?,
             club: std::env::var("ELKATO_CLUB")?,
         },
-    )?;
+    )?
+    .with_timezone(match std::env::var("ELKATO_TIMEZONE") {
+        Ok(tz) => tz
+            .parse()
+            .map_err(|err| anyhow!("Invalid timezone: {err}"))?,
+        Err(_) => DEFAULT_TIMEZONE,
+    });
+
+    // record the session, e.g. for attaching it to a bug report
+    let record = std::env::var("ELKATO_RECORD").ok();
+    let recorder = Recorder::new();
+    let api = match record {
+        Some(_) => api.with_recorder(recorder.clone()),
+        None => api,
+    };
 
     let today = Utc::now().date_naive();
 
-    let bookings: Vec<Booking> = api
+    let result: Result<Vec<Booking>, _> = api
         .list_bookings(ListOptions {
             owner: Some("demo".into()),
             start_from: Some(today - Duration::days(7)),
@@ -31,7 +48,15 @@ async fn main() -> anyhow::Result<()> {
         })
         .boxed_local()
         .try_collect()
-        .await?;
+        .await;
+
+    // keep the recording of a failed session too
+    if let Some(path) = record {
+        recorder.cassette().save(&path)?;
+        println!("Recorded session: {path}");
+    }
+
+    let bookings = result?;
 
     println!("Bookings ({}):", bookings.len());
 
@@ -0,0 +1,187 @@
+//! Finding free slots of a resource.
+
+use crate::Booking;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A time interval, with an inclusive start and an exclusive end.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interval {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl Interval {
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self { start, end }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+
+    /// Check if the interval overlaps with a booking.
+    pub fn overlaps(&self, booking: &Booking) -> bool {
+        booking.start < self.end && booking.end > self.start
+    }
+
+    /// Get the part of the interval which is covered by the booking.
+    pub fn overlap(&self, booking: &Booking) -> Option<Interval> {
+        self.overlaps(booking)
+            .then(|| Interval::new(self.start.max(booking.start), self.end.min(booking.end)))
+    }
+}
+
+/// An existing booking clashing with a proposed one.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Conflict {
+    /// The existing booking, including its owner.
+    pub booking: Booking,
+    /// The time both bookings have in common.
+    pub overlap: Interval,
+}
+
+/// Find all bookings clashing with the proposed interval.
+///
+/// The bookings must all belong to the same resource.
+pub fn find_conflicts<'a, I>(bookings: I, proposed: Interval) -> Vec<Conflict>
+where
+    I: IntoIterator<Item = &'a Booking>,
+{
+    let mut result: Vec<_> = bookings
+        .into_iter()
+        .filter_map(|booking| {
+            proposed.overlap(booking).map(|overlap| Conflict {
+                booking: booking.clone(),
+                overlap,
+            })
+        })
+        .collect();
+    result.sort_by_key(|c| c.overlap.start);
+    result
+}
+
+/// Compute the free intervals of a window, which are at least `min_duration` long.
+///
+/// The bookings must all belong to the same resource. Bookings outside the window are ignored,
+/// overlapping bookings are merged.
+pub fn free_slots<'a, I>(bookings: I, window: Interval, min_duration: Duration) -> Vec<Interval>
+where
+    I: IntoIterator<Item = &'a Booking>,
+{
+    let mut bookings: Vec<_> = bookings
+        .into_iter()
+        .filter(|b| window.overlaps(b))
+        .collect();
+    bookings.sort_by_key(|b| b.start);
+
+    let mut result = Vec::new();
+    let mut cursor = window.start;
+
+    for booking in bookings {
+        if booking.start > cursor {
+            result.push(Interval::new(cursor, booking.start));
+        }
+        cursor = cursor.max(booking.end);
+    }
+
+    if cursor < window.end {
+        result.push(Interval::new(cursor, window.end));
+    }
+
+    result.retain(|slot| slot.duration() >= min_duration);
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2023, 2, 11, hour, min, 0).unwrap()
+    }
+
+    fn booking(start: DateTime<Utc>, end: DateTime<Utc>) -> Booking {
+        Booking {
+            id: "1".into(),
+            resource: "Transporter".into(),
+            owner: "demo".into(),
+            booker: "demo".into(),
+            start,
+            end,
+            duration: None,
+            description: String::new(),
+            columns: vec![],
+            location: None,
+            cancelled: false,
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let window = Interval::new(at(8, 0), at(18, 0));
+        assert_eq!(free_slots(&[], window, Duration::hours(1)), vec![window]);
+    }
+
+    #[test]
+    fn merge_overlapping() {
+        let bookings = [
+            booking(at(9, 0), at(11, 0)),
+            booking(at(10, 0), at(12, 0)),
+            booking(at(14, 0), at(15, 0)),
+            // outside of the window
+            booking(at(19, 0), at(20, 0)),
+        ];
+
+        assert_eq!(
+            free_slots(
+                &bookings,
+                Interval::new(at(8, 0), at(18, 0)),
+                Duration::zero()
+            ),
+            vec![
+                Interval::new(at(8, 0), at(9, 0)),
+                Interval::new(at(12, 0), at(14, 0)),
+                Interval::new(at(15, 0), at(18, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn conflicts() {
+        let bookings = [
+            booking(at(7, 0), at(9, 0)),
+            booking(at(9, 30), at(12, 0)),
+            booking(at(12, 0), at(13, 0)),
+        ];
+
+        let conflicts = find_conflicts(&bookings, Interval::new(at(8, 0), at(10, 0)));
+
+        assert_eq!(
+            conflicts.iter().map(|c| c.overlap).collect::<Vec<_>>(),
+            vec![
+                Interval::new(at(8, 0), at(9, 0)),
+                Interval::new(at(9, 30), at(10, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn min_duration() {
+        let bookings = [
+            booking(at(7, 0), at(9, 0)),
+            booking(at(9, 30), at(12, 0)),
+            booking(at(17, 0), at(19, 0)),
+        ];
+
+        assert_eq!(
+            free_slots(
+                &bookings,
+                Interval::new(at(8, 0), at(18, 0)),
+                Duration::hours(1)
+            ),
+            vec![Interval::new(at(12, 0), at(17, 0))]
+        );
+    }
+}
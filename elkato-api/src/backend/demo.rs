@@ -0,0 +1,87 @@
+use super::MemoryBackend;
+use crate::{Booking, Resource};
+use chrono::{DateTime, Duration, DurationRound, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+const RESOURCES: &[(&str, &str, Option<&str>)] = &[
+    ("1", "VW Golf", Some("PKW")),
+    ("2", "Opel Corsa", Some("PKW")),
+    ("3", "Renault Zoe", Some("PKW")),
+    ("4", "VW Transporter", Some("Transporter")),
+    ("5", "Lastenrad", None),
+    ("6", "Gemeinschaftsraum", None),
+];
+
+const OTHERS: [&str; 3] = ["anna", "bernd", "claudia"];
+
+/// Create a backend with sample data, for demonstrating the frontend without Elkato.
+///
+/// The bookings are relative to `now`: the user has a past booking, one which is currently
+/// active, and a few upcoming ones. Other users hold bookings as well, one of them made on
+/// behalf of the user. Bookings of the same resource never overlap.
+pub fn demo(username: &str, now: DateTime<Utc>, timezone: Tz) -> MemoryBackend {
+    let backend = MemoryBackend::new(username)
+        .with_timezone(timezone)
+        .with_resources(RESOURCES.iter().map(|(id, name, group)| Resource {
+            id: id.to_string(),
+            name: name.to_string(),
+            group: group.map(ToString::to_string),
+        }));
+
+    let today = now.with_timezone(&timezone).date_naive();
+    let at = |days: i64, hour: u32| {
+        let local =
+            (today + Duration::days(days)).and_time(NaiveTime::from_hms_opt(hour, 0, 0).unwrap());
+        timezone
+            .from_local_datetime(&local)
+            .earliest()
+            .unwrap_or_else(|| timezone.from_utc_datetime(&local))
+            .with_timezone(&Utc)
+    };
+    let hour = now.duration_trunc(Duration::hours(1)).unwrap_or(now);
+
+    let user = username;
+    let [anna, bernd, claudia] = OTHERS;
+    let current = (hour - Duration::hours(1), hour + Duration::hours(2));
+
+    // (resource, owner, booker, start, end, description, active)
+    #[rustfmt::skip]
+    let bookings = [
+        ("1", user, user, at(-3, 9), at(-3, 13), "Einkauf", true),
+        ("5", user, user, at(-1, 15), at(-1, 17), "", true),
+        ("2", user, user, current.0, current.1, "Arzttermin", true),
+        ("4", user, user, at(1, 8), at(1, 12), "Umzug", true),
+        ("6", user, anna, at(2, 18), at(2, 22), "Geburtstag", true),
+        ("1", user, user, at(4, 7), at(6, 20), "Wochenendausflug", true),
+        ("3", user, user, at(3, 10), at(3, 12), "", false),
+        ("1", anna, anna, at(0, 7), at(0, 9), "", true),
+        ("1", bernd, bernd, at(1, 9), at(1, 17), "Dienstreise", true),
+        ("2", claudia, claudia, at(1, 10), at(1, 14), "", true),
+        ("4", bernd, bernd, at(3, 6), at(3, 18), "Möbeltransport", true),
+        ("5", claudia, claudia, at(2, 9), at(2, 11), "Wochenmarkt", true),
+        ("3", anna, anna, at(-2, 8), at(-2, 18), "", true),
+    ];
+
+    for (id, (resource, owner, booker, start, end, description, active)) in
+        bookings.into_iter().enumerate()
+    {
+        backend.add_booking(
+            resource,
+            Booking {
+                id: (1001 + id).to_string(),
+                resource: resource.to_string(),
+                owner: owner.to_string(),
+                booker: booker.to_string(),
+                start,
+                end,
+                duration: Some((end - start).num_minutes() as u32),
+                description: description.to_string(),
+                columns: vec![],
+                location: None,
+                cancelled: !active,
+            },
+        );
+    }
+
+    backend
+}
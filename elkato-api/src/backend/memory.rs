@@ -0,0 +1,256 @@
+use super::BookingBackend;
+use crate::{
+    availability::{find_conflicts, Interval},
+    Booking, BookingDetails, BookingError, BookingState, Error, ListOptions, NewBooking, Resource,
+    DEFAULT_TIMEZONE,
+};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use futures::future::{ready, FutureExt, LocalBoxFuture};
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Debug)]
+struct Entry {
+    /// The ID of the resource, as the booking only carries its name.
+    resource: String,
+    booking: Booking,
+}
+
+#[derive(Debug)]
+struct State {
+    resources: Vec<Resource>,
+    entries: Vec<Entry>,
+    next_id: u32,
+}
+
+/// A backend keeping all bookings in memory, acting as the user it was created for.
+///
+/// It follows the rules of Elkato: bookings of a resource must not overlap, and only the owner
+/// may cancel a booking, before it started. Clones share the same bookings.
+#[derive(Clone, Debug)]
+pub struct MemoryBackend {
+    username: String,
+    timezone: Tz,
+    state: Arc<Mutex<State>>,
+}
+
+impl MemoryBackend {
+    pub fn new(username: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            timezone: DEFAULT_TIMEZONE,
+            state: Arc::new(Mutex::new(State {
+                resources: vec![],
+                entries: vec![],
+                next_id: 1,
+            })),
+        }
+    }
+
+    /// Set the timezone, which the date filters of [`ListOptions`] refer to.
+    pub fn with_timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    pub fn with_resources<I>(self, resources: I) -> Self
+    where
+        I: IntoIterator<Item = Resource>,
+    {
+        self.state.lock().unwrap().resources.extend(resources);
+        self
+    }
+
+    /// Add an existing booking of a resource, by the resource's ID.
+    ///
+    /// The booking is stored as it is, without checking for conflicts, and with the name of the
+    /// resource filled in.
+    pub fn add_booking(&self, resource: &str, mut booking: Booking) {
+        let mut state = self.state.lock().unwrap();
+
+        if let Ok(id) = booking.id.parse::<u32>() {
+            state.next_id = state.next_id.max(id + 1);
+        }
+        if let Some(r) = state.resources.iter().find(|r| r.id == resource) {
+            booking.resource = r.name.clone();
+        }
+
+        state.entries.push(Entry {
+            resource: resource.to_string(),
+            booking,
+        });
+    }
+
+    /// All bookings, including the cancelled ones.
+    pub fn bookings(&self) -> Vec<Booking> {
+        self.state
+            .lock()
+            .unwrap()
+            .entries
+            .iter()
+            .map(|e| e.booking.clone())
+            .collect()
+    }
+
+    fn matches(&self, entry: &Entry, options: &ListOptions) -> bool {
+        let date = |datetime: &DateTime<Utc>| datetime.with_timezone(&self.timezone).date_naive();
+        let booking = &entry.booking;
+        let start = date(&booking.start);
+        let end = date(&booking.end);
+
+        let state = match options.state {
+            BookingState::Active => !booking.cancelled,
+            BookingState::Inactive => booking.cancelled,
+            BookingState::All => true,
+        };
+
+        state
+            && options
+                .resource
+                .as_ref()
+                .is_none_or(|r| r == &entry.resource)
+            && options.owner.as_ref().is_none_or(|o| o == &booking.owner)
+            && options.booker.as_ref().is_none_or(|b| b == &booking.booker)
+            && options.start_from.is_none_or(|d| start >= d)
+            && options.start_to.is_none_or(|d| start <= d)
+            && options.end_from.is_none_or(|d| end >= d)
+            && options.end_to.is_none_or(|d| end <= d)
+    }
+
+    fn list(&self, options: &ListOptions) -> Vec<Booking> {
+        let mut result: Vec<_> = self
+            .state
+            .lock()
+            .unwrap()
+            .entries
+            .iter()
+            .filter(|e| self.matches(e, options))
+            .map(|e| e.booking.clone())
+            .collect();
+        result.sort_by(|a, b| a.start.cmp(&b.start).then_with(|| a.id.cmp(&b.id)));
+        result
+    }
+
+    fn get(&self, id: &str) -> Result<BookingDetails, Error> {
+        let state = self.state.lock().unwrap();
+        let entry = state
+            .entries
+            .iter()
+            .find(|e| e.booking.id == id)
+            .ok_or(BookingError::NotFound)?;
+
+        Ok(BookingDetails {
+            booking: entry.booking.clone(),
+            creator: Some(entry.booking.booker.clone()),
+            created: None,
+            modified: None,
+            fields: vec![],
+        })
+    }
+
+    fn create(&self, booking: NewBooking) -> Result<Booking, Error> {
+        if booking.start >= booking.end {
+            return Err(Error::InvalidRequest(
+                "The start of a booking must be before its end".into(),
+            ));
+        }
+
+        let mut state = self.state.lock().unwrap();
+
+        let resource = state
+            .resources
+            .iter()
+            .find(|r| r.id == booking.resource)
+            .cloned()
+            .ok_or_else(|| {
+                Error::InvalidRequest(format!("Unknown resource: {}", booking.resource))
+            })?;
+
+        let existing = state
+            .entries
+            .iter()
+            .filter(|e| !e.booking.cancelled && e.resource == resource.id)
+            .map(|e| &e.booking);
+        if let Some(conflict) =
+            find_conflicts(existing, Interval::new(booking.start, booking.end)).first()
+        {
+            return Err(BookingError::Conflict(format!(
+                "Die Buchung überschneidet sich mit der Buchung {}",
+                conflict.booking.id
+            ))
+            .into());
+        }
+
+        let id = state.next_id;
+        state.next_id += 1;
+
+        let result = Booking {
+            id: id.to_string(),
+            resource: resource.name,
+            owner: booking.owner.unwrap_or_else(|| self.username.clone()),
+            booker: self.username.clone(),
+            start: booking.start,
+            end: booking.end,
+            duration: None,
+            description: booking.description,
+            columns: vec![],
+            location: None,
+            cancelled: false,
+        };
+
+        state.entries.push(Entry {
+            resource: resource.id,
+            booking: result.clone(),
+        });
+
+        Ok(result)
+    }
+
+    fn cancel(&self, id: &str) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        let entry = state
+            .entries
+            .iter_mut()
+            .find(|e| e.booking.id == id && !e.booking.cancelled)
+            .ok_or(BookingError::NotFound)?;
+
+        if entry.booking.owner != self.username {
+            return Err(BookingError::NotOwner.into());
+        }
+        if entry.booking.start <= Utc::now() {
+            return Err(BookingError::AlreadyStarted.into());
+        }
+
+        entry.booking.cancelled = true;
+        Ok(())
+    }
+}
+
+impl BookingBackend for MemoryBackend {
+    fn verify_credentials(&self) -> LocalBoxFuture<'_, Result<(), Error>> {
+        ready(Ok(())).boxed_local()
+    }
+
+    fn list_resources(&self) -> LocalBoxFuture<'_, Result<Vec<Resource>, Error>> {
+        ready(Ok(self.state.lock().unwrap().resources.clone())).boxed_local()
+    }
+
+    fn list_bookings(
+        &self,
+        options: ListOptions,
+    ) -> LocalBoxFuture<'_, Result<Vec<Booking>, Error>> {
+        ready(Ok(self.list(&options))).boxed_local()
+    }
+
+    fn get_booking<'a>(&'a self, id: &'a str) -> LocalBoxFuture<'a, Result<BookingDetails, Error>> {
+        ready(self.get(id)).boxed_local()
+    }
+
+    fn create_booking(&self, booking: NewBooking) -> LocalBoxFuture<'_, Result<Booking, Error>> {
+        ready(self.create(booking)).boxed_local()
+    }
+
+    fn cancel_booking<'a>(&'a self, id: &'a str) -> LocalBoxFuture<'a, Result<(), Error>> {
+        ready(self.cancel(id)).boxed_local()
+    }
+}
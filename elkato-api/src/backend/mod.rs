@@ -0,0 +1,118 @@
+//! Abstracting over where bookings come from.
+//!
+//! The [`BookingBackend`] trait covers the operations the frontend needs. It is implemented by
+//! the [`Api`], talking to Elkato, and by the [`MemoryBackend`], which keeps everything in memory.
+//! The [`demo`] function creates a memory backend populated with sample data.
+
+mod demo;
+mod memory;
+
+pub use demo::demo;
+pub use memory::MemoryBackend;
+
+use crate::{
+    availability::{find_conflicts, Conflict, Interval},
+    cors::CorsProxy,
+    Api, Booking, BookingDetails, Error, ListOptions, NewBooking, Resource,
+};
+use chrono::Duration;
+use futures::{future::LocalBoxFuture, FutureExt, StreamExt, TryStreamExt};
+
+/// A source of bookings, which also allows creating and cancelling them.
+///
+/// The futures are not required to be `Send`, as the frontend runs single threaded.
+pub trait BookingBackend {
+    /// Verify that the backend accepts the credentials.
+    fn verify_credentials(&self) -> LocalBoxFuture<'_, Result<(), Error>>;
+
+    /// List the resources which can be booked.
+    fn list_resources(&self) -> LocalBoxFuture<'_, Result<Vec<Resource>, Error>>;
+
+    /// List all bookings matching the options.
+    fn list_bookings(
+        &self,
+        options: ListOptions,
+    ) -> LocalBoxFuture<'_, Result<Vec<Booking>, Error>>;
+
+    /// Get the full details of a booking.
+    fn get_booking<'a>(&'a self, id: &'a str) -> LocalBoxFuture<'a, Result<BookingDetails, Error>>;
+
+    /// Create a new booking.
+    fn create_booking(&self, booking: NewBooking) -> LocalBoxFuture<'_, Result<Booking, Error>>;
+
+    /// Cancel a booking.
+    fn cancel_booking<'a>(&'a self, id: &'a str) -> LocalBoxFuture<'a, Result<(), Error>>;
+
+    /// Describe how the backend is reached, for showing it to the user.
+    fn connection(&self) -> Option<String> {
+        None
+    }
+
+    /// Find all active bookings which clash with a proposed booking of a resource.
+    fn check_conflicts<'a>(
+        &'a self,
+        resource: &'a str,
+        proposed: Interval,
+    ) -> LocalBoxFuture<'a, Result<Vec<Conflict>, Error>> {
+        // the date filters are in the club's timezone, so add a day of margin on each side
+        let options = ListOptions {
+            resource: Some(resource.to_string()),
+            start_to: Some((proposed.end + Duration::days(1)).date_naive()),
+            end_from: Some((proposed.start - Duration::days(1)).date_naive()),
+            ..Default::default()
+        };
+
+        async move {
+            let bookings = self.list_bookings(options).await?;
+            Ok(find_conflicts(&bookings, proposed))
+        }
+        .boxed_local()
+    }
+}
+
+impl BookingBackend for Api {
+    fn connection(&self) -> Option<String> {
+        Some(match self.proxy() {
+            CorsProxy::None => "Elkato".to_string(),
+            proxy => format!("Elkato, through {proxy}"),
+        })
+    }
+
+    fn verify_credentials(&self) -> LocalBoxFuture<'_, Result<(), Error>> {
+        Api::verify_credentials(self).boxed_local()
+    }
+
+    fn list_resources(&self) -> LocalBoxFuture<'_, Result<Vec<Resource>, Error>> {
+        Api::list_resources(self).boxed_local()
+    }
+
+    fn list_bookings(
+        &self,
+        options: ListOptions,
+    ) -> LocalBoxFuture<'_, Result<Vec<Booking>, Error>> {
+        Api::list_bookings(self, options)
+            .boxed_local()
+            .try_collect()
+            .boxed_local()
+    }
+
+    fn get_booking<'a>(&'a self, id: &'a str) -> LocalBoxFuture<'a, Result<BookingDetails, Error>> {
+        Api::get_booking(self, id).boxed_local()
+    }
+
+    fn create_booking(&self, booking: NewBooking) -> LocalBoxFuture<'_, Result<Booking, Error>> {
+        Api::create_booking(self, booking).boxed_local()
+    }
+
+    fn cancel_booking<'a>(&'a self, id: &'a str) -> LocalBoxFuture<'a, Result<(), Error>> {
+        Api::cancel_booking(self, id).boxed_local()
+    }
+
+    fn check_conflicts<'a>(
+        &'a self,
+        resource: &'a str,
+        proposed: Interval,
+    ) -> LocalBoxFuture<'a, Result<Vec<Conflict>, Error>> {
+        Api::check_conflicts(self, resource, proposed).boxed_local()
+    }
+}
@@ -1,11 +1,141 @@
-use reqwest::RequestBuilder;
-use url::Url;
+use reqwest::{
+    header::{HeaderName, AUTHORIZATION, CONTENT_LOCATION},
+    Request, RequestBuilder,
+};
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use url::{Position, Url};
+
+/// The header `elkato-proxy` restores the `Authorization` header from, by default.
+pub const PROXY_AUTHORIZATION_HEADER: &str = "x-proxy-authorization";
 
 #[derive(Clone, Debug)]
 pub enum CorsProxy {
     None,
+    /// Append the target URL to the path of the proxy, like `https://proxy/cors/https://target/`.
+    ///
+    /// The query of the target gets appended to the query of the proxy URL, like an API key.
     Prepend(Url),
-    Query { url: Url, parameter: String },
+    /// Send the target URL in a query parameter of the proxy.
+    Query {
+        url: Url,
+        parameter: String,
+    },
+    /// Send the credentials in a custom header, instead of the `Authorization` header.
+    ///
+    /// Some proxies drop the `Authorization` header. This requires a proxy which restores it from
+    /// the custom header, like `elkato-proxy` does.
+    AuthorizationHeader {
+        proxy: Box<CorsProxy>,
+        header: HeaderName,
+    },
+    /// Try multiple proxies, in order, until one works.
+    Fallback(Fallback),
+}
+
+/// A list of proxies, remembering the last one which worked.
+///
+/// Clones share which proxy is in use.
+#[derive(Clone, Debug)]
+pub struct Fallback {
+    proxies: Vec<CorsProxy>,
+    current: Arc<AtomicUsize>,
+}
+
+impl Fallback {
+    pub fn new(proxies: Vec<CorsProxy>) -> Self {
+        Self {
+            proxies,
+            current: Default::default(),
+        }
+    }
+
+    pub fn proxies(&self) -> &[CorsProxy] {
+        &self.proxies
+    }
+
+    /// The proxy which is currently in use.
+    pub fn current(&self) -> &CorsProxy {
+        self.proxies
+            .get(self.current.load(Ordering::Relaxed))
+            .unwrap_or(&CorsProxy::None)
+    }
+
+    /// Execute a request, starting with the current proxy, and moving on to the next one on
+    /// network errors and server errors.
+    ///
+    /// Requests which aren't idempotent, like creating a booking, might have been processed
+    /// despite an error. They only move on if the proxy couldn't be connected to.
+    async fn execute(
+        &self,
+        client: &reqwest::Client,
+        mut req: Request,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let len = self.proxies.len();
+        if len == 0 {
+            return client.execute(req).await;
+        }
+
+        let start = self.current.load(Ordering::Relaxed);
+        let idempotent = req.method().is_idempotent();
+
+        for attempt in 0..len {
+            let index = (start + attempt) % len;
+            let proxy = &self.proxies[index];
+
+            // keep a copy for the next attempt, if there is one
+            let next = match attempt + 1 < len {
+                true => req.try_clone(),
+                false => None,
+            };
+
+            proxy.apply(&mut req);
+            let result = client.execute(req).await;
+            let failed = match &result {
+                Ok(response) => idempotent && response.status().is_server_error(),
+                Err(err) => err.is_connect() || (idempotent && !err.is_builder()),
+            };
+
+            match next {
+                Some(next) if failed => {
+                    log::warn!("Proxy {proxy} failed, trying the next one");
+                    req = next;
+                }
+                _ => {
+                    if !failed {
+                        self.current.store(index, Ordering::Relaxed);
+                    }
+                    return result;
+                }
+            }
+        }
+
+        unreachable!("The last attempt always returns")
+    }
+}
+
+impl From<Vec<CorsProxy>> for Fallback {
+    fn from(proxies: Vec<CorsProxy>) -> Self {
+        Self::new(proxies)
+    }
+}
+
+/// Describes the proxy in use, for showing it to the user.
+impl fmt::Display for CorsProxy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => f.write_str("direct"),
+            Self::Prepend(url) => write!(f, "{url}"),
+            Self::Query { url, .. } => write!(f, "{url}"),
+            Self::AuthorizationHeader { proxy, .. } => proxy.fmt(f),
+            Self::Fallback(fallback) => fallback.current().fmt(f),
+        }
+    }
 }
 
 impl CorsProxy {
@@ -14,13 +144,125 @@ impl CorsProxy {
         client: &reqwest::Client,
         builder: RequestBuilder,
     ) -> Result<reqwest::Response, reqwest::Error> {
-        let mut req = builder.build()?;
+        self.execute(client, builder.build()?).await
+    }
+
+    /// Send the credentials in the default header of `elkato-proxy`.
+    pub fn with_authorization_header(self) -> Self {
+        Self::AuthorizationHeader {
+            proxy: Box::new(self),
+            header: HeaderName::from_static(PROXY_AUTHORIZATION_HEADER),
+        }
+    }
+
+    /// Try the proxies in order, until one works.
+    pub fn fallback(proxies: Vec<CorsProxy>) -> Self {
+        Self::Fallback(Fallback::new(proxies))
+    }
+
+    /// The proxy which is currently in use, resolving fallbacks.
+    pub fn current(&self) -> &CorsProxy {
+        match self {
+            Self::Fallback(fallback) => fallback.current().current(),
+            _ => self,
+        }
+    }
+
+    /// Execute a request, through the proxy.
+    pub async fn execute(
+        &self,
+        client: &reqwest::Client,
+        mut req: Request,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        match self {
+            Self::Fallback(fallback) => fallback.execute(client, req).await,
+            _ => {
+                self.apply(&mut req);
+                client.execute(req).await
+            }
+        }
+    }
+
+    /// The URL of Elkato a response came from, instead of the URL of the proxy.
+    ///
+    /// Proxies follow redirects on behalf of the client. `elkato-proxy` reports the URL it ended
+    /// up at in the `Content-Location` header, others only tell the URL which got requested.
+    pub fn target_url(&self, response: &reqwest::Response) -> Url {
+        let url = response.url();
+        match self.resolve(url) {
+            Some(target) if &target != url => response
+                .headers()
+                .get(CONTENT_LOCATION)
+                .and_then(|location| location.to_str().ok())
+                .and_then(|location| target.join(location).ok())
+                .unwrap_or(target),
+            _ => url.clone(),
+        }
+    }
+
+    /// Extract the target from a URL rewritten by [`CorsProxy::apply`], or `None` if the URL
+    /// doesn't point to the proxy.
+    fn resolve(&self, url: &Url) -> Option<Url> {
+        match self {
+            Self::None => Some(url.clone()),
+            Self::Prepend(proxy) => {
+                if url.origin() != proxy.origin() {
+                    return None;
+                }
+                let path = url
+                    .path()
+                    .strip_prefix(proxy.path().trim_end_matches('/'))?
+                    .strip_prefix('/')?;
+                let mut target = Url::parse(path).ok()?;
+
+                // drop the query of the proxy, which comes first
+                let query = url.query().unwrap_or_default();
+                let query = match proxy.query().filter(|query| !query.is_empty()) {
+                    Some(prefix) => query.strip_prefix(prefix)?.trim_start_matches('&'),
+                    None => query,
+                };
+                target.set_query(Some(query).filter(|query| !query.is_empty()));
+                Some(target)
+            }
+            Self::Query {
+                url: proxy,
+                parameter,
+            } => {
+                if url.origin() != proxy.origin() || url.path() != proxy.path() {
+                    return None;
+                }
+                url.query_pairs()
+                    .find(|(name, _)| name == parameter)
+                    .and_then(|(_, target)| Url::parse(&target).ok())
+            }
+            Self::AuthorizationHeader { proxy, .. } => proxy.resolve(url),
+            // the response might come from any of them, but most likely from the current one
+            Self::Fallback(fallback) => std::iter::once(fallback.current())
+                .chain(fallback.proxies())
+                .find_map(|proxy| proxy.resolve(url)),
+        }
+    }
 
+    /// Rewrite a request to go through the proxy.
+    fn apply(&self, req: &mut Request) {
         match self {
             Self::None => {}
             Self::Prepend(proxy) => {
+                let target = req.url().clone();
                 let mut proxy = proxy.clone();
-                proxy.set_path(req.url().as_str());
+                proxy.set_path(&format!(
+                    "{}/{}",
+                    proxy.path().trim_end_matches('/'),
+                    &target[..Position::AfterPath]
+                ));
+                let query = [proxy.query(), target.query()]
+                    .into_iter()
+                    .flatten()
+                    .filter(|query| !query.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("&");
+                proxy.set_query(Some(query.as_str()).filter(|query| !query.is_empty()));
+                proxy.set_fragment(None);
                 *req.url_mut() = proxy;
             }
             Self::Query { url, parameter } => {
@@ -30,8 +272,14 @@ impl CorsProxy {
                     .append_pair(parameter, req.url().as_str());
                 *req.url_mut() = proxy;
             }
+            Self::AuthorizationHeader { proxy, header } => {
+                if let Some(value) = req.headers_mut().remove(AUTHORIZATION) {
+                    req.headers_mut().insert(header.clone(), value);
+                }
+                proxy.apply(req);
+            }
+            // nested fallbacks don't switch, but stick to their current proxy
+            Self::Fallback(fallback) => fallback.current().apply(req),
         }
-
-        client.execute(req).await
     }
 }
@@ -0,0 +1,116 @@
+use reqwest::StatusCode;
+
+/// An error working with the Elkato API.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Elkato rejected the credentials.
+    #[error("Invalid credentials")]
+    Unauthorized,
+    /// Elkato doesn't know the club.
+    #[error("Unknown club")]
+    UnknownClub,
+    /// Failed to reach Elkato, through the CORS proxy if one is configured.
+    #[error("Failed to contact the server: {0}")]
+    ProxyFailure(#[source] reqwest::Error),
+    /// The server responded with an unexpected status code.
+    #[error("Unexpected HTTP status: {0}")]
+    HttpStatus(StatusCode),
+    /// The response didn't look like expected, most likely the Elkato layout changed.
+    #[error("Failed to parse response: {context}")]
+    ParseError { context: String },
+    /// A date or time could not be parsed or converted.
+    #[error("Invalid date: {0}")]
+    InvalidDate(String),
+    /// Elkato rejected the request with a message we don't know.
+    #[error("Rejected by server: {0}")]
+    ServerMessage(String),
+    /// A failure reported by Elkato when working with a booking.
+    #[error(transparent)]
+    Booking(#[from] BookingError),
+    /// The request could not be built.
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    /// Replaying a cassette, which has no response for the request.
+    #[error("No recorded response for: {0}")]
+    NotRecorded(String),
+}
+
+impl Error {
+    /// Check if the error is transient, and the operation might succeed when being retried.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::ProxyFailure(_) => true,
+            Self::HttpStatus(status) => {
+                status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+            }
+            _ => false,
+        }
+    }
+
+    pub(crate) fn parse(context: impl Into<String>) -> Self {
+        Self::ParseError {
+            context: context.into(),
+        }
+    }
+
+    /// Classify an error message rendered by Elkato.
+    pub(crate) fn from_message(message: String) -> Self {
+        let lower = message.to_lowercase();
+
+        if lower.contains("nicht der besitzer")
+            || lower.contains("keine berechtigung")
+            || lower.contains("nicht berechtigt")
+        {
+            BookingError::NotOwner.into()
+        } else if lower.contains("bereits begonnen") || lower.contains("schon begonnen") {
+            BookingError::AlreadyStarted.into()
+        } else if lower.contains("überschneid")
+            || lower.contains("kollidiert")
+            || lower.contains("bereits gebucht")
+            || lower.contains("bereits belegt")
+        {
+            BookingError::Conflict(message).into()
+        } else if lower.contains("existiert nicht") || lower.contains("nicht gefunden") {
+            BookingError::NotFound.into()
+        } else {
+            Self::ServerMessage(message)
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_builder() {
+            return Self::InvalidRequest(err.to_string());
+        }
+
+        match err.status() {
+            Some(StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) => Self::Unauthorized,
+            Some(status) => Self::HttpStatus(status),
+            None => Self::ProxyFailure(err),
+        }
+    }
+}
+
+impl From<url::ParseError> for Error {
+    fn from(err: url::ParseError) -> Self {
+        Self::InvalidRequest(format!("Invalid URL: {err}"))
+    }
+}
+
+/// A failure reported by Elkato when working with a booking.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum BookingError {
+    /// The booking belongs to someone else.
+    #[error("The booking belongs to a different user")]
+    NotOwner,
+    /// The booking already started and can no longer be changed.
+    #[error("The booking has already started")]
+    AlreadyStarted,
+    /// The booking doesn't exist.
+    #[error("The booking could not be found")]
+    NotFound,
+    /// The booking overlaps with an existing booking.
+    #[error("Conflicting booking: {0}")]
+    Conflict(String),
+}
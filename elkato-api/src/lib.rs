@@ -1,18 +1,30 @@
+pub mod availability;
+pub mod backend;
 pub mod model;
 
+mod error;
 mod parser;
 mod utils;
 
 pub mod cors;
+pub mod record;
+pub use error::*;
 pub use model::*;
+pub use parser::{Column, ColumnWarning, ParseReport, SkipReason, SkippedRow};
 
 use crate::{
+    availability::{find_conflicts, free_slots, Conflict, Interval},
     cors::CorsProxy,
-    utils::{date_filter_to_query, make_url},
+    record::{Cassette, Mode, Player, Recorder},
+    utils::{date_filter_to_query, datetime_to_form, make_url},
+};
+use chrono::{Duration, NaiveDate};
+use chrono_tz::Tz;
+use futures::{stream, Stream, StreamExt, TryStreamExt};
+use reqwest::{
+    header::{self, HeaderValue},
+    StatusCode,
 };
-use chrono::NaiveDate;
-use futures::{stream, StreamExt, TryStreamExt};
-use reqwest::header::{self, HeaderValue};
 use std::sync::Arc;
 use url::{ParseError, Url};
 
@@ -28,6 +40,24 @@ pub struct Api {
     frontend_url: Url,
     proxy: Arc<CorsProxy>,
     credentials: Credentials,
+    timezone: Tz,
+    mode: Mode,
+}
+
+/// The timezone Elkato uses, unless configured otherwise.
+pub const DEFAULT_TIMEZONE: Tz = chrono_tz::Europe::Berlin;
+
+/// Read the body of a response, ensuring we didn't end up on the login or an error page.
+fn read_body(resp: record::Response) -> Result<String, Error> {
+    // a redirect to the login page means that the credentials are not accepted, checking the
+    // URL of Elkato, not the one of the CORS proxy
+    if resp.url.path().contains("login") {
+        return Err(Error::Unauthorized);
+    }
+
+    parser::check_access(&resp.body)?;
+
+    Ok(resp.body)
 }
 
 #[derive(Clone, Debug, Default)]
@@ -40,7 +70,12 @@ pub enum BookingState {
 
 #[derive(Clone, Debug, Default)]
 pub struct ListOptions {
+    /// Limit to a resource, by its ID.
+    pub resource: Option<String>,
+    /// Limit to bookings held by a user.
     pub owner: Option<String>,
+    /// Limit to bookings made by a user, possibly on behalf of someone else.
+    pub booker: Option<String>,
     pub start_from: Option<NaiveDate>,
     pub start_to: Option<NaiveDate>,
     pub end_from: Option<NaiveDate>,
@@ -53,7 +88,7 @@ impl Api {
         frontend_url: Url,
         proxy: CorsProxy,
         credentials: Credentials,
-    ) -> anyhow::Result<Self> {
+    ) -> Result<Self, Error> {
         let mut headers = header::HeaderMap::new();
 
         headers.insert(
@@ -70,17 +105,283 @@ impl Api {
             frontend_url,
             proxy: Arc::new(proxy),
             credentials,
+            timezone: DEFAULT_TIMEZONE,
+            mode: Mode::Live,
         })
     }
 
+    /// Record all requests and responses, with credentials redacted.
+    pub fn with_recorder(mut self, recorder: Recorder) -> Self {
+        self.mode = Mode::Record { recorder };
+        self
+    }
+
+    /// Serve all responses from a cassette, instead of contacting Elkato.
+    pub fn with_replay(mut self, cassette: Cassette) -> Self {
+        self.mode = Mode::Replay {
+            player: Player::new(cassette),
+        };
+        self
+    }
+
+    /// Set the timezone of the club, in which Elkato renders and expects dates and times.
+    pub fn with_timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// The timezone of the club.
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+    }
+
+    /// The CORS proxy currently in use, which might change when using a fallback.
+    pub fn proxy(&self) -> &CorsProxy {
+        self.proxy.current()
+    }
+
     fn url(&self, path: &str) -> Result<Url, ParseError> {
         self.frontend_url.join(path)
     }
 
+    /// Execute a request to Elkato, authenticated and through the CORS proxy.
+    async fn execute(&self, builder: reqwest::RequestBuilder) -> Result<record::Response, Error> {
+        let builder = builder.basic_auth(
+            self.credentials.username.clone(),
+            Some(self.credentials.password.clone()),
+        );
+
+        self.mode.execute(&self.proxy, &self.client, builder).await
+    }
+
+    /// Send a request to Elkato, failing on a non-success status.
+    async fn send(&self, builder: reqwest::RequestBuilder) -> Result<record::Response, Error> {
+        self.execute(builder).await?.error_for_status()
+    }
+
+    /// Verify that Elkato accepts the credentials and knows the club.
+    pub async fn verify_credentials(&self) -> Result<(), Error> {
+        let builder = self
+            .client
+            .get(self.url("search.php")?)
+            .query(&[("club", self.credentials.club.as_str())]);
+
+        read_body(self.send(builder).await?)?;
+
+        Ok(())
+    }
+
+    /// List the resources of the club.
+    pub async fn list_resources(&self) -> Result<Vec<Resource>, Error> {
+        let builder = self
+            .client
+            .get(self.url("search.php")?)
+            .query(&[("club", self.credentials.club.as_str())]);
+
+        let resp = self.send(builder).await?;
+
+        parser::parse_resources(&read_body(resp)?)
+    }
+
+    /// List all active bookings of a resource, which might overlap with the interval.
+    async fn overlapping_bookings(
+        &self,
+        resource: &str,
+        interval: Interval,
+    ) -> Result<Vec<Booking>, Error> {
+        let tz = self.timezone;
+
+        self.list_bookings(ListOptions {
+            resource: Some(resource.to_string()),
+            start_to: Some(interval.end.with_timezone(&tz).date_naive()),
+            end_from: Some(interval.start.with_timezone(&tz).date_naive()),
+            ..Default::default()
+        })
+        .boxed_local()
+        .try_collect()
+        .await
+    }
+
+    /// Find the free slots of a resource in a time window, which are at least `min_duration` long.
+    pub async fn availability(
+        &self,
+        resource: &str,
+        window: Interval,
+        min_duration: Duration,
+    ) -> Result<Vec<Interval>, Error> {
+        let bookings = self.overlapping_bookings(resource, window).await?;
+        Ok(free_slots(&bookings, window, min_duration))
+    }
+
+    /// Find all existing bookings which clash with a proposed booking of a resource.
+    pub async fn check_conflicts(
+        &self,
+        resource: &str,
+        proposed: Interval,
+    ) -> Result<Vec<Conflict>, Error> {
+        let bookings = self.overlapping_bookings(resource, proposed).await?;
+        Ok(find_conflicts(&bookings, proposed))
+    }
+
+    /// Get the full details of a booking.
+    pub async fn get_booking(&self, id: &str) -> Result<BookingDetails, Error> {
+        let builder = self
+            .client
+            .get(self.url("view_entry.php")?)
+            .query(&[("club", self.credentials.club.as_str()), ("id", id)]);
+
+        let resp = self.execute(builder).await?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Err(BookingError::NotFound.into());
+        }
+
+        let mut details =
+            parser::parse_details(id, &read_body(resp.error_for_status()?)?, self.timezone)?;
+        details.booking.location = make_url(id, &self.frontend_url, &self.credentials).ok();
+
+        Ok(details)
+    }
+
+    /// Create a new booking, by submitting the booking entry form.
+    pub async fn create_booking(&self, booking: NewBooking) -> Result<Booking, Error> {
+        self.submit_entry(None, booking).await
+    }
+
+    /// Change an existing booking.
+    ///
+    /// This loads the current state of the booking, applies the changes, and submits the result.
+    pub async fn update_booking(
+        &self,
+        id: &str,
+        changes: BookingChanges,
+    ) -> Result<Booking, Error> {
+        let builder = self
+            .client
+            .get(self.url("edit_entry.php")?)
+            .query(&[("club", self.credentials.club.as_str()), ("id", id)]);
+
+        let resp = self.execute(builder).await?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Err(BookingError::NotFound.into());
+        }
+
+        let current =
+            parser::parse_entry_form(&read_body(resp.error_for_status()?)?, self.timezone)?;
+
+        self.submit_entry(Some(id), changes.apply(current)).await
+    }
+
+    /// Submit the booking entry form, either for a new or an existing booking.
+    ///
+    /// Returns the booking as stored by Elkato.
+    async fn submit_entry(&self, id: Option<&str>, booking: NewBooking) -> Result<Booking, Error> {
+        if booking.start >= booking.end {
+            return Err(Error::InvalidRequest(
+                "The start of a booking must be before its end".into(),
+            ));
+        }
+
+        let owner = booking
+            .owner
+            .clone()
+            .unwrap_or_else(|| self.credentials.username.clone());
+
+        let mut form = vec![
+            ("club".to_string(), self.credentials.club.clone()),
+            ("room".to_string(), booking.resource.clone()),
+            ("create_by".to_string(), owner),
+            ("description".to_string(), booking.description.clone()),
+        ];
+        if let Some(id) = id {
+            form.push(("id".to_string(), id.to_string()));
+        }
+        form.extend(datetime_to_form("start", &booking.start, self.timezone));
+        form.extend(datetime_to_form("end", &booking.end, self.timezone));
+
+        let builder = self
+            .client
+            .post(self.url("edit_entry_handler.php")?)
+            .form(&form);
+        let resp = self.send(builder).await?;
+
+        log::debug!("URL: {}", resp.url);
+
+        // a successful request might get redirected to the entry
+        let id = match parser::entry_id_from_href(resp.url.as_str()) {
+            Some(id) => id,
+            None => parser::parse_entry_response(&read_body(resp)?)?,
+        };
+
+        // read back the entry, which carries the name of the resource instead of its ID, like
+        // the bookings of a search do
+        Ok(self.get_booking(&id).await?.booking)
+    }
+
+    /// Cancel a booking, keeping it as an inactive booking.
+    pub async fn cancel_booking(&self, id: &str) -> Result<(), Error> {
+        self.modify_booking("cancel_entry.php", id).await
+    }
+
+    /// Delete a booking.
+    pub async fn delete_booking(&self, id: &str) -> Result<(), Error> {
+        self.modify_booking("del_entry.php", id).await
+    }
+
+    async fn modify_booking(&self, path: &str, id: &str) -> Result<(), Error> {
+        let builder = self
+            .client
+            .post(self.url(path)?)
+            .form(&[("club", self.credentials.club.as_str()), ("id", id)]);
+
+        let resp = self.execute(builder).await?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Err(BookingError::NotFound.into());
+        }
+
+        parser::parse_modify_response(&read_body(resp.error_for_status()?)?)
+    }
+
     pub fn list_bookings(
         &self,
         options: ListOptions,
-    ) -> impl TryStreamExt<Item = anyhow::Result<Booking>> {
+    ) -> impl TryStreamExt<Item = Result<Booking, Error>> {
+        self.list_pages(options)
+            .map_ok(|(bookings, report)| {
+                for warning in &report.warnings {
+                    log::warn!("Unexpected search result layout: {warning}");
+                }
+                for row in &report.skipped {
+                    log::warn!("Skipped search result ({}): {:?}", row.reason, row.cells);
+                }
+                stream::iter(bookings).map(Ok)
+            })
+            .try_flatten()
+    }
+
+    /// List bookings, like [`Api::list_bookings`], but also report what could not be parsed.
+    ///
+    /// This allows telling an empty calendar apart from a result which we failed to understand.
+    pub async fn list_bookings_with_diagnostics(
+        &self,
+        options: ListOptions,
+    ) -> Result<(Vec<Booking>, ParseReport), Error> {
+        self.list_pages(options)
+            .try_fold(
+                (Vec::new(), ParseReport::default()),
+                |(mut bookings, mut report), (page, page_report)| async move {
+                    bookings.extend(page);
+                    report.extend(page_report);
+                    Ok((bookings, report))
+                },
+            )
+            .await
+    }
+
+    /// Fetch all pages of a search, along with the parse report of each page.
+    fn list_pages(
+        &self,
+        options: ListOptions,
+    ) -> impl Stream<Item = Result<(Vec<Booking>, ParseReport), Error>> {
         #[derive(Clone, Debug)]
         struct ListState {
             credentials: Credentials,
@@ -90,6 +391,8 @@ impl Api {
             proxy: Arc<CorsProxy>,
             frontend_url: Url,
             options: ListOptions,
+            timezone: Tz,
+            mode: Mode,
         }
 
         let url = self.url("search.php");
@@ -105,6 +408,8 @@ impl Api {
             client,
             credentials: self.credentials.clone(),
             options,
+            timezone: self.timezone,
+            mode: self.mode.clone(),
         };
 
         stream::try_unfold(init, move |state| {
@@ -115,7 +420,7 @@ impl Api {
 
                 match state.offset {
                     // having no offset means, we finish up in the last iteration
-                    None => Result::<_, anyhow::Error>::Ok(None),
+                    None => Result::<_, Error>::Ok(None),
                     // having an offset means we need to pull in more data
                     Some(offset) => {
                         let builder = state
@@ -128,8 +433,14 @@ impl Api {
                             .query(&[
                                 ("club", state.credentials.club.clone()),
                                 ("search_pos", format!("{}", offset)),
-                                ("sel_room", "all".into()),
-                                ("sel_booker", "all".into()),
+                                (
+                                    "sel_room",
+                                    state.options.resource.unwrap_or_else(|| "all".into()),
+                                ),
+                                (
+                                    "sel_booker",
+                                    state.options.booker.unwrap_or_else(|| "all".into()),
+                                ),
                                 (
                                     "sel_owner",
                                     state.options.owner.unwrap_or_else(|| "all".into()),
@@ -153,33 +464,35 @@ impl Api {
 
                         //let resp = builder.send().await?.error_for_status()?;
                         let resp = state
-                            .proxy
-                            .send(&state.client, builder)
+                            .mode
+                            .execute(&state.proxy, &state.client, builder)
                             .await?
                             .error_for_status()?;
 
-                        log::debug!("URL: {}", resp.url());
+                        log::debug!("URL: {}", resp.url);
 
-                        let result = parser::parse_query(&resp.text().await?)?;
+                        let result = parser::parse_query(&read_body(resp)?, state.timezone)?;
 
                         let next_offset = match result.paging {
                             None => None,
                             Some(p) if p.to >= p.total => None,
+                            // the server didn't advance, stop instead of looping forever
+                            Some(p) if p.from <= offset => None,
                             Some(p) => Some(p.to),
                         };
 
-                        let context = (next.frontend_url.clone(), next.credentials.clone());
-
-                        let y = stream::iter(result.bookings)
-                            .map(move |b| {
-                                let mut b = b.clone();
-                                b.location = make_url(&b.id, &context.0, &context.1).ok();
+                        let bookings = result
+                            .bookings
+                            .into_iter()
+                            .map(|mut b| {
+                                b.location =
+                                    make_url(&b.id, &next.frontend_url, &next.credentials).ok();
                                 b
                             })
-                            .map(|b| Ok(b));
+                            .collect();
 
                         Ok(Some((
-                            y,
+                            (bookings, result.report),
                             ListState {
                                 offset: next_offset,
                                 ..next
@@ -189,6 +502,5 @@ impl Api {
                 }
             }
         })
-        .try_flatten()
     }
 }
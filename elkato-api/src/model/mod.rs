@@ -4,15 +4,71 @@ use serde::{Deserialize, Serialize};
 use url::Url;
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "StoredBooking")]
 pub struct Booking {
     pub id: String,
     pub resource: String,
-    pub user: String,
+    /// The user holding the booking.
+    pub owner: String,
+    /// The user who made the booking, possibly on behalf of the owner.
+    pub booker: String,
     pub start: DateTime<Utc>,
     pub end: DateTime<Utc>,
+    /// The duration in minutes, as reported by Elkato.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u32>,
     pub description: String,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// Additional, club specific columns of the search result, as label and value.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub location: Option<Url>,
+    /// Cancelled bookings are kept by Elkato, as inactive bookings. Only search results tell.
+    pub cancelled: bool,
+}
+
+/// A booking, as serialized, which might be from before the booker was known.
+///
+/// This is where the attributes for reading older data go, [`Booking`] only gets deserialized
+/// through it.
+#[derive(Deserialize)]
+struct StoredBooking {
+    id: String,
+    resource: String,
+    #[serde(alias = "user")]
+    owner: String,
+    #[serde(default)]
+    booker: Option<String>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    #[serde(default)]
+    duration: Option<u32>,
+    description: String,
+    #[serde(default)]
+    columns: Vec<(String, String)>,
+    #[serde(default)]
+    location: Option<Url>,
+    #[serde(default)]
+    cancelled: bool,
+}
+
+/// Without a booker, it's the owner who made the booking, like when parsing search results.
+impl From<StoredBooking> for Booking {
+    fn from(stored: StoredBooking) -> Self {
+        Self {
+            booker: stored.booker.unwrap_or_else(|| stored.owner.clone()),
+            id: stored.id,
+            resource: stored.resource,
+            owner: stored.owner,
+            start: stored.start,
+            end: stored.end,
+            duration: stored.duration,
+            description: stored.description,
+            columns: stored.columns,
+            location: stored.location,
+            cancelled: stored.cancelled,
+        }
+    }
 }
 
 impl Booking {
@@ -24,3 +80,95 @@ impl Booking {
         now >= &self.end
     }
 }
+
+/// A bookable resource, like a room or a vehicle.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Resource {
+    /// The ID, as used by the `sel_room` filter and the booking entry form.
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+}
+
+/// The full details of a booking, as shown on its detail page.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookingDetails {
+    pub booking: Booking,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creator: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<DateTime<Utc>>,
+    /// Additional, club specific fields, as label and value.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<(String, String)>,
+}
+
+/// A booking to be created.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewBooking {
+    /// The resource to book.
+    pub resource: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    #[serde(default)]
+    pub description: String,
+    /// The user to create the booking for, defaults to the current user.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+}
+
+/// Changes to an existing booking. Fields which are `None` stay unchanged.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookingChanges {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl BookingChanges {
+    /// Apply the changes to the current state of a booking.
+    pub fn apply(self, mut booking: NewBooking) -> NewBooking {
+        if let Some(resource) = self.resource {
+            booking.resource = resource;
+        }
+        if let Some(start) = self.start {
+            booking.start = start;
+        }
+        if let Some(end) = self.end {
+            booking.end = end;
+        }
+        if let Some(description) = self.description {
+            booking.description = description;
+        }
+        booking
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn booking_without_booker() {
+        let booking: Booking = serde_json::from_str(
+            r#"{
+                "id": "1", "resource": "Lastenrad", "user": "demo", "description": "",
+                "start": "2023-02-11T10:00:00Z", "end": "2023-02-11T12:00:00Z"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(booking.owner, "demo");
+        assert_eq!(booking.booker, "demo");
+
+        let json = serde_json::to_string(&booking).unwrap();
+        assert_eq!(serde_json::from_str::<Booking>(&json).unwrap(), booking);
+    }
+}
@@ -1,95 +1,572 @@
-use crate::Booking;
-use anyhow::anyhow;
-use chrono::{DateTime, LocalResult, NaiveDateTime, Utc};
+use crate::{Booking, BookingDetails, Error, NewBooking, Resource};
+use chrono::{
+    DateTime, Duration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Utc,
+};
+use chrono_tz::Tz;
 use itertools::Itertools;
-use scraper::{ElementRef, Selector};
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use serde::Serialize;
 
-#[derive(Copy, Clone, Debug)]
+mod report;
+mod table;
+
+pub use report::{ParseReport, SkipReason, SkippedRow};
+use table::Columns;
+pub use table::{Column, ColumnWarning};
+
+#[derive(Copy, Clone, Debug, Serialize)]
 pub struct Paging {
     pub from: usize,
     pub to: usize,
     pub total: usize,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct ListResponse {
     pub paging: Option<Paging>,
     pub bookings: Vec<Booking>,
+    pub report: ParseReport,
 }
 
-pub(crate) fn parse_query(body: &str) -> anyhow::Result<ListResponse> {
+pub(crate) fn parse_query(body: &str, tz: Tz) -> Result<ListResponse, Error> {
     log::debug!("Payload: {}", body);
 
     if body.contains("<B>Die Suche ergab keine Treffer!</B>") {
         return Ok(ListResponse {
             paging: None,
             bookings: vec![],
+            report: Default::default(),
         });
     }
 
-    parse_bookings_list(body)
+    parse_bookings_list(body, tz)
 }
 
-fn parse_bookings_list(body: &str) -> anyhow::Result<ListResponse> {
+fn parse_bookings_list(body: &str, tz: Tz) -> Result<ListResponse, Error> {
     let html = scraper::Html::parse_document(body);
 
     log::info!("Errors: {:?}", html.errors);
     log::info!("  Quirks Mode: {:?}", html.quirks_mode);
     //log::info!("  Tree: {:#?}", html.tree);
 
-    let sel = selector(r##"#pageBody table[bordercolor="#000000"] tbody tr"##)?;
-    //log::info!("Selector: {sel:?}");
+    let tables = selector(r##"#pageBody table[bordercolor="#000000"]"##)?;
+    let rows = selector("tr")?;
+    let headers = selector("th")?;
+    let cells = selector("td")?;
+    // inactive bookings, like cancelled ones, are rendered grayed out
+    let inactive = selector(r##"td font[color="#999999"]"##)?;
+
+    let mut bookings = Vec::new();
+    let mut report = ParseReport::default();
+
+    for table in html.select(&tables) {
+        let mut columns = None;
+
+        for row in table.select(&rows) {
+            let labels: Vec<String> = row
+                .select(&headers)
+                .map(|cell| cell.text().collect())
+                .collect();
+            if !labels.is_empty() {
+                let header = Columns::from_header(labels);
+                log::debug!("Columns: {header:?}");
+                // without a required column, each row gets reported as skipped
+                report.warnings.extend(header.warnings());
+                columns = Some(header);
+                continue;
+            }
+
+            let cells: Vec<String> = row
+                .select(&cells)
+                .map(|cell| cell.text().collect())
+                .collect();
+            if cells.is_empty() {
+                continue;
+            }
+
+            let columns = columns.get_or_insert_with(|| {
+                report.warnings.push(ColumnWarning::NoHeader);
+                Columns::default()
+            });
+
+            match parse_row(&cells, columns, tz) {
+                Ok(booking) => bookings.push(Booking {
+                    cancelled: row.select(&inactive).next().is_some(),
+                    ..booking
+                }),
+                Err(reason) => {
+                    log::debug!("Skipping row ({reason}): {cells:?}");
+                    report.skipped.push(SkippedRow { cells, reason });
+                }
+            }
+        }
+    }
 
     Ok(ListResponse {
-        bookings: html
-            .select(&sel)
-            .filter_map::<anyhow::Result<Booking>, _>(|row| parse_row(row).transpose())
-            .collect::<Result<Vec<_>, _>>()?,
-        paging: None,
+        bookings,
+        paging: parse_paging(&html)?,
+        report,
     })
 }
 
-const DATE_TIME_FORMAT: &str = "%d.%m.%y, %H:%M";
+/// Check if Elkato rendered a login or error page instead of the requested content.
+///
+/// Only the error block rendered by Elkato is checked for known messages, as the rest of the page
+/// may contain user provided text, like the description of a booking.
+pub(crate) fn check_access(body: &str) -> Result<(), Error> {
+    const UNKNOWN_CLUB: &[&str] = &[
+        "unbekannter verein",
+        "unbekannter club",
+        "verein existiert nicht",
+        "club existiert nicht",
+        "verein nicht gefunden",
+        "club nicht gefunden",
+    ];
+    const UNAUTHORIZED: &[&str] = &[
+        "falsches passwort",
+        "ungültiges passwort",
+        "ungültiger benutzer",
+        "anmeldung fehlgeschlagen",
+        "zugriff verweigert",
+    ];
 
-fn parse_row(row: ElementRef) -> anyhow::Result<Option<Booking>> {
-    let cells: Vec<String> = row
-        .select(&selector("td")?)
-        .map(|cell| cell.text().collect())
-        .collect();
+    let html = scraper::Html::parse_document(body);
 
-    log::info!("Cells: {cells:?}");
+    if let Some(message) = parse_error_message(&html)? {
+        let lower = message.to_lowercase();
+        if UNKNOWN_CLUB.iter().any(|s| lower.contains(s)) {
+            return Err(Error::UnknownClub);
+        }
+        if UNAUTHORIZED.iter().any(|s| lower.contains(s)) {
+            return Err(Error::Unauthorized);
+        }
+    }
 
-    if let Some((id, resource, user, _, start, _, end, _duration, _, description)) =
-        cells.into_iter().tuples().next()
+    // the login form, unless the page has the content we asked for
+    let content = selector(
+        r##"#pageBody table[bordercolor="#000000"], form[action*="search.php"], form[action*="edit_entry"]"##,
+    )?;
+    if html.select(&content).next().is_none()
+        && html
+            .select(&selector(r#"form input[type="password"]"#)?)
+            .next()
+            .is_some()
     {
-        let start = to_datetime(&start)?;
-        let end = to_datetime(&end)?;
+        return Err(Error::Unauthorized);
+    }
+
+    Ok(())
+}
+
+/// Parse the "hits x - y of z" information, rendered above and below the result table.
+///
+/// The numbers are one-based and inclusive, so that `to` is the offset of the next page.
+fn parse_paging(html: &Html) -> Result<Option<Paging>, Error> {
+    let re = Regex::new(
+        r"(?i)(?:Treffer|Datensätze|Hits|Records):?\s*(\d+)\s*(?:-|–|bis|to|through)\s*(\d+)\s*(?:von|of)\s*(\d+)",
+    )
+    .map_err(|err| Error::parse(format!("Invalid paging expression: {err}")))?;
+
+    let text = html
+        .select(&selector("#pageBody")?)
+        .flat_map(|body| body.text())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Ok(re.captures(&text).and_then(|cap| {
+        let from = cap[1].parse().ok()?;
+        let to = cap[2].parse().ok()?;
+        let total = cap[3].parse().ok()?;
+
+        log::debug!("Paging: {from} - {to} of {total}");
+
+        Some(Paging { from, to, total })
+    }))
+}
+
+/// Parse the response of the booking entry form handler.
+///
+/// Returns the ID of the booking if the server accepted it, or the server's message if it
+/// rejected it.
+pub(crate) fn parse_entry_response(body: &str) -> Result<String, Error> {
+    log::debug!("Payload: {}", body);
+
+    let html = scraper::Html::parse_document(body);
+
+    if let Some(message) = parse_error_message(&html)? {
+        return Err(Error::from_message(message));
+    }
+
+    let sel = selector(r#"#pageBody a[href*="view_entry.php"]"#)?;
+    html.select(&sel)
+        .filter_map(|link| link.value().attr("href"))
+        .find_map(entry_id_from_href)
+        .ok_or_else(|| Error::parse("Unable to find booking ID in response"))
+}
+
+/// Parse the response of cancelling or deleting a booking.
+pub(crate) fn parse_modify_response(body: &str) -> Result<(), Error> {
+    log::debug!("Payload: {}", body);
+
+    let html = scraper::Html::parse_document(body);
+
+    match parse_error_message(&html)? {
+        Some(message) => Err(Error::from_message(message)),
+        None => Ok(()),
+    }
+}
+
+/// Parse the booking entry form, pre-filled with the state of an existing booking.
+pub(crate) fn parse_entry_form(body: &str, tz: Tz) -> Result<NewBooking, Error> {
+    log::debug!("Payload: {}", body);
+
+    let html = scraper::Html::parse_document(body);
+
+    if let Some(message) = parse_error_message(&html)? {
+        return Err(Error::from_message(message));
+    }
+
+    let field = |name: &str| -> Result<String, Error> {
+        form_value(&html, name)?.ok_or_else(|| Error::parse(format!("Missing form field: {name}")))
+    };
+    let datetime = |prefix: &str| -> Result<NaiveDateTime, Error> {
+        let number = |name: &str| -> Result<u32, Error> {
+            let name = format!("{prefix}_{name}");
+            field(&name)?
+                .trim()
+                .parse()
+                .map_err(|err| Error::parse(format!("Invalid value for {name}: {err}")))
+        };
+
+        let date =
+            NaiveDate::from_ymd_opt(number("year")? as i32, number("month")?, number("day")?)
+                .ok_or_else(|| Error::InvalidDate(format!("Invalid {prefix} date")))?;
+        let time = NaiveTime::from_hms_opt(number("hour")?, number("minute")?, 0)
+            .ok_or_else(|| Error::InvalidDate(format!("Invalid {prefix} time")))?;
+
+        Ok(date.and_time(time))
+    };
+    let (start, end) = from_local_range(datetime("start")?, datetime("end")?, tz);
+
+    Ok(NewBooking {
+        resource: field("room")?,
+        start,
+        end,
+        description: form_value(&html, "description")?.unwrap_or_default(),
+        owner: form_value(&html, "create_by")?,
+    })
+}
+
+/// Get the current value of a form field, which may be an input, a select, or a text area.
+fn form_value(html: &Html, name: &str) -> Result<Option<String>, Error> {
+    let input = selector(&format!(r#"form input[name="{name}"]"#))?;
+    if let Some(value) = html.select(&input).find_map(|e| e.value().attr("value")) {
+        return Ok(Some(value.to_string()));
+    }
+
+    let option = selector(&format!(r#"form select[name="{name}"] option[selected]"#))?;
+    if let Some(option) = html.select(&option).next() {
+        return Ok(Some(
+            option
+                .value()
+                .attr("value")
+                .map(ToString::to_string)
+                .unwrap_or_else(|| option.text().collect()),
+        ));
+    }
+
+    let textarea = selector(&format!(r#"form textarea[name="{name}"]"#))?;
+    Ok(html.select(&textarea).next().map(|e| e.text().collect()))
+}
+
+/// Extract an error message the server rendered into the page.
+fn parse_error_message(html: &Html) -> Result<Option<String>, Error> {
+    let sel = selector(
+        r##"#pageBody .error, #pageBody font[color="red"], #pageBody font[color="#FF0000"]"##,
+    )?;
 
-        Ok(Some(Booking {
-            id,
-            resource,
-            user,
+    let message = html
+        .select(&sel)
+        .map(|e| e.text().collect::<String>().trim().to_string())
+        .filter(|text| !text.is_empty())
+        .join(" ");
+
+    Ok(if message.is_empty() {
+        None
+    } else {
+        Some(message)
+    })
+}
+
+/// Get the booking ID from a (relative) link to `view_entry.php`.
+pub(crate) fn entry_id_from_href(href: &str) -> Option<String> {
+    let (path, query) = href.split_once('?')?;
+    if !path.ends_with("view_entry.php") {
+        return None;
+    }
+    url::form_urlencoded::parse(query.as_bytes())
+        .find(|(k, _)| k == "id")
+        .map(|(_, v)| v.into_owned())
+}
+
+/// Parse the resources from the resource selector of the search form.
+pub(crate) fn parse_resources(body: &str) -> Result<Vec<Resource>, Error> {
+    log::debug!("Payload: {}", body);
+
+    let html = scraper::Html::parse_document(body);
+
+    let sel = selector(r#"select[name="sel_room"] option"#)?;
+
+    Ok(html
+        .select(&sel)
+        .filter_map(|option| {
+            let id = option.value().attr("value")?.trim().to_string();
+            if id.is_empty() || id == "all" {
+                return None;
+            }
+
+            let group = option
+                .parent()
+                .and_then(ElementRef::wrap)
+                .filter(|parent| parent.value().name() == "optgroup")
+                .and_then(|parent| parent.value().attr("label"))
+                .map(|label| label.trim().to_string());
+
+            Some(Resource {
+                id,
+                name: option.text().collect::<String>().trim().to_string(),
+                group,
+            })
+        })
+        .collect())
+}
+
+const DATE_TIME_FORMAT: &str = "%d.%m.%y, %H:%M";
+
+/// Formats used for timestamps on the detail page.
+const DETAIL_DATE_TIME_FORMATS: &[&str] = &[
+    DATE_TIME_FORMAT,
+    "%d.%m.%Y, %H:%M",
+    "%d.%m.%Y %H:%M",
+    "%d.%m.%Y %H:%M:%S",
+];
+
+/// Parse the detail page of a booking (`view_entry.php`).
+pub(crate) fn parse_details(id: &str, body: &str, tz: Tz) -> Result<BookingDetails, Error> {
+    log::debug!("Payload: {}", body);
+
+    let html = scraper::Html::parse_document(body);
+
+    if let Some(message) = parse_error_message(&html)? {
+        return Err(Error::from_message(message));
+    }
+
+    let sel = selector("#pageBody table tr")?;
+    let cells = selector("td, th")?;
+
+    let mut resource = None;
+    let mut owner = None;
+    let mut booker = None;
+    let mut start = None;
+    let mut end = None;
+    let mut description = String::new();
+    let mut creator = None;
+    let mut created = None;
+    let mut modified = None;
+    let mut fields = Vec::new();
+
+    for row in html.select(&sel) {
+        let Some((label, value)) = row
+            .select(&cells)
+            .map(|cell| cell.text().collect::<String>().trim().to_string())
+            .collect_tuple()
+        else {
+            continue;
+        };
+
+        match label.trim_end_matches(':').to_lowercase().as_str() {
+            "nr." | "nr" | "id" => {}
+            "ressource" | "raum" | "fahrzeug" => resource = Some(value),
+            "benutzer" | "inhaber" => owner = Some(value),
+            "gebucht von" => booker = Some(value),
+            "beginn" => start = Some(value),
+            "ende" => end = Some(value),
+            "bemerkung" | "beschreibung" => description = value,
+            "erstellt von" => creator = Some(value),
+            "erstellt am" => created = to_detail_datetime(&value, tz),
+            "geändert am" | "letzte änderung" => modified = to_detail_datetime(&value, tz),
+            _ => fields.push((label.trim_end_matches(':').to_string(), value)),
+        }
+    }
+
+    let owner = owner.ok_or_else(|| Error::parse("Missing owner"))?;
+    let (start, end) = to_range(
+        &start.ok_or_else(|| Error::parse("Missing start"))?,
+        &end.ok_or_else(|| Error::parse("Missing end"))?,
+        tz,
+    )
+    .map_err(Error::InvalidDate)?;
+    // older entries only know the creator, which is the one who booked it
+    let booker = booker
+        .or_else(|| creator.clone())
+        .unwrap_or_else(|| owner.clone());
+
+    Ok(BookingDetails {
+        booking: Booking {
+            id: id.to_string(),
+            resource: resource.ok_or_else(|| Error::parse("Missing resource"))?,
+            owner,
+            booker,
             start,
             end,
+            duration: None,
             description,
+            columns: vec![],
             location: None,
-        }))
-    } else {
-        Ok(None)
+            cancelled: false,
+        },
+        creator,
+        created,
+        modified,
+        fields,
+    })
+}
+
+/// Parse a timestamp on the detail page, which might not be present at all.
+fn to_detail_datetime(value: &str, tz: Tz) -> Option<DateTime<Utc>> {
+    DETAIL_DATE_TIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|datetime| from_local(datetime, tz))
+}
+
+fn parse_row(cells: &[String], columns: &Columns, tz: Tz) -> Result<Booking, SkipReason> {
+    log::debug!("Cells: {cells:?}");
+
+    let mut id = None;
+    let mut resource = None;
+    let mut owner = None;
+    let mut start = None;
+    let mut end = None;
+    let mut duration = None;
+    let mut booker = None;
+    let mut description = None;
+    let mut additional = Vec::new();
+
+    for (index, value) in cells.iter().enumerate() {
+        let value = value.trim().to_string();
+        match columns.get(index) {
+            Some((_, Some(Column::Id))) => id = Some(value),
+            Some((_, Some(Column::Resource))) => resource = Some(value),
+            Some((_, Some(Column::Owner))) => owner = Some(value),
+            Some((_, Some(Column::Weekday))) => {}
+            Some((_, Some(Column::Start))) => start = Some(value),
+            Some((_, Some(Column::End))) => end = Some(value),
+            Some((_, Some(Column::Duration))) => duration = to_minutes(&value),
+            Some((_, Some(Column::Booker))) => booker = Some(value),
+            Some((_, Some(Column::Description))) => description = Some(value),
+            Some((label, None)) => additional.push((label.to_string(), value)),
+            None => log::debug!("Ignoring cell #{index} without a column: {value}"),
+        }
     }
+
+    let required = |value: Option<String>, column| {
+        value
+            .filter(|value| !value.is_empty())
+            .ok_or(SkipReason::MissingValue { column })
+    };
+    let id = required(id, Column::Id)?;
+    let start = required(start, Column::Start)?;
+    let end = required(end, Column::End)?;
+
+    let (start, end) = to_range(&start, &end, tz).map_err(SkipReason::InvalidDate)?;
+    let owner = owner.unwrap_or_default();
+
+    Ok(Booking {
+        id,
+        resource: resource.unwrap_or_default(),
+        booker: booker.unwrap_or_else(|| owner.clone()),
+        owner,
+        start,
+        end,
+        duration,
+        description: description.unwrap_or_default(),
+        columns: additional,
+        location: None,
+        cancelled: false,
+    })
 }
 
-fn selector(sel: &str) -> anyhow::Result<Selector> {
-    Ok(Selector::parse(sel).map_err(|err| anyhow!("Failed to parse selector: {err}"))?)
+/// Parse a duration, as rendered by Elkato (`h:mm`), into minutes.
+fn to_minutes(value: &str) -> Option<u32> {
+    let (hours, minutes) = value.trim().split_once(':')?;
+    Some(hours.trim().parse::<u32>().ok()? * 60 + minutes.trim().parse::<u32>().ok()?)
 }
 
-fn to_datetime(datetime: &str) -> anyhow::Result<DateTime<Utc>> {
-    match NaiveDateTime::parse_from_str(datetime, DATE_TIME_FORMAT)?
-        .and_local_timezone(chrono_tz::Europe::Berlin)
-        .map(|dt| dt.with_timezone(&Utc))
-    {
-        LocalResult::None => Err(anyhow!("Failed to convert date/time")),
-        LocalResult::Single(datetime) => Ok(datetime),
-        LocalResult::Ambiguous(datetime, _) => Ok(datetime),
+fn selector(sel: &str) -> Result<Selector, Error> {
+    Selector::parse(sel).map_err(|err| Error::parse(format!("Failed to parse selector: {err}")))
+}
+
+/// Parse a date/time, as rendered by Elkato, failing with a message naming the invalid value.
+fn to_naive(datetime: &str) -> Result<NaiveDateTime, String> {
+    NaiveDateTime::parse_from_str(datetime, DATE_TIME_FORMAT)
+        .map_err(|err| format!("{datetime}: {err}"))
+}
+
+/// Parse the start and end of a booking, as rendered by Elkato in the club's timezone.
+fn to_range(start: &str, end: &str, tz: Tz) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+    Ok(from_local_range(to_naive(start)?, to_naive(end)?, tz))
+}
+
+/// Convert the start and end of a booking from the club's timezone.
+///
+/// An ambiguous start resolves to its first occurrence. An ambiguous end resolves to the first
+/// occurrence after the start, so that a booking like 02:40 → 02:10 during the switch back from
+/// daylight saving time ends up being 30 minutes long. Nonexistent times are handled like in
+/// [`from_local`].
+fn from_local_range(
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    tz: Tz,
+) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start = from_local(start, tz);
+    let end = match end.and_local_timezone(tz) {
+        LocalResult::Ambiguous(first, second) => {
+            let first = first.with_timezone(&Utc);
+            if first > start {
+                first
+            } else {
+                second.with_timezone(&Utc)
+            }
+        }
+        _ => from_local(end, tz),
+    };
+
+    (start, end)
+}
+
+/// Convert a date/time from the club's timezone.
+///
+/// An ambiguous time, which occurs twice when switching back from daylight saving time, resolves
+/// to its first occurrence.
+///
+/// A nonexistent time, which falls into the gap when switching to daylight saving time, gets
+/// shifted forward by the length of the gap. Like a clock which wasn't adjusted yet, 02:30 is
+/// treated as 03:30 when the clocks jump from 02:00 to 03:00.
+fn from_local(datetime: NaiveDateTime, tz: Tz) -> DateTime<Utc> {
+    match datetime.and_local_timezone(tz) {
+        LocalResult::Single(datetime) => datetime.with_timezone(&Utc),
+        LocalResult::Ambiguous(datetime, _) => datetime.with_timezone(&Utc),
+        LocalResult::None => {
+            // use the offset from before the gap, a day earlier is safely outside of it
+            let offset = tz
+                .offset_from_utc_datetime(&(datetime - Duration::days(1)))
+                .fix();
+            Utc.from_utc_datetime(&(datetime - Duration::seconds(offset.local_minus_utc().into())))
+        }
     }
 }
+
+#[cfg(test)]
+mod test;
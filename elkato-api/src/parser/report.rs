@@ -0,0 +1,58 @@
+use super::{Column, ColumnWarning};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// Issues found while parsing search results.
+///
+/// An empty report means that every row of the result table turned into a booking.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseReport {
+    /// Issues with the layout of the result table.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<ColumnWarning>,
+    /// Rows which could not be turned into a booking.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<SkippedRow>,
+}
+
+impl ParseReport {
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty() && self.skipped.is_empty()
+    }
+
+    /// Add the report of another page, dropping duplicate warnings.
+    pub fn extend(&mut self, other: ParseReport) {
+        for warning in other.warnings {
+            if !self.warnings.contains(&warning) {
+                self.warnings.push(warning);
+            }
+        }
+        self.skipped.extend(other.skipped);
+    }
+}
+
+/// A row of the result table which could not be turned into a booking.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedRow {
+    /// The text of each cell, as rendered by Elkato.
+    pub cells: Vec<String>,
+    pub reason: SkipReason,
+}
+
+/// The reason a row was skipped.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SkipReason {
+    /// The row has no value for a column we require.
+    MissingValue { column: Column },
+    /// The start or end could not be parsed.
+    InvalidDate(String),
+}
+
+impl Display for SkipReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingValue { column } => write!(f, "Missing value: {column}"),
+            Self::InvalidDate(err) => write!(f, "Invalid date: {err}"),
+        }
+    }
+}
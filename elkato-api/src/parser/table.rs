@@ -0,0 +1,177 @@
+//! Mapping the columns of the search result table.
+//!
+//! Clubs can configure which columns the search result shows, so we read the header row instead
+//! of relying on a fixed order.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// A column of the search result table we know about.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Column {
+    Id,
+    Resource,
+    Owner,
+    /// The day of the week, rendered in front of the start and end, which we don't need.
+    Weekday,
+    Start,
+    End,
+    Duration,
+    Booker,
+    Description,
+}
+
+impl Column {
+    /// Columns we expect to find, and warn about when they are missing.
+    const EXPECTED: [Column; 8] = [
+        Self::Id,
+        Self::Resource,
+        Self::Owner,
+        Self::Start,
+        Self::End,
+        Self::Duration,
+        Self::Booker,
+        Self::Description,
+    ];
+
+    /// The layout Elkato used before rendering a header row.
+    const LEGACY: [Column; 10] = [
+        Self::Id,
+        Self::Resource,
+        Self::Owner,
+        Self::Weekday,
+        Self::Start,
+        Self::Weekday,
+        Self::End,
+        Self::Duration,
+        Self::Booker,
+        Self::Description,
+    ];
+
+    fn from_label(label: &str) -> Option<Self> {
+        Some(
+            match label
+                .trim()
+                .trim_end_matches(['.', ':'])
+                .to_lowercase()
+                .as_str()
+            {
+                "nr" | "id" | "nummer" => Self::Id,
+                "ressource" | "raum" | "fahrzeug" => Self::Resource,
+                "benutzer" | "inhaber" => Self::Owner,
+                "tag" | "wochentag" => Self::Weekday,
+                "beginn" | "start" => Self::Start,
+                "ende" => Self::End,
+                "dauer" => Self::Duration,
+                "gebucht von" => Self::Booker,
+                "bemerkung" | "beschreibung" => Self::Description,
+                _ => return None,
+            },
+        )
+    }
+}
+
+impl Display for Column {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Id => "Nr.",
+            Self::Resource => "Ressource",
+            Self::Owner => "Benutzer",
+            Self::Weekday => "Tag",
+            Self::Start => "Beginn",
+            Self::End => "Ende",
+            Self::Duration => "Dauer",
+            Self::Booker => "Gebucht von",
+            Self::Description => "Bemerkung",
+        })
+    }
+}
+
+/// An issue with the layout of the search result table.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColumnWarning {
+    /// The table has no header row, the legacy layout was assumed.
+    NoHeader,
+    /// A column we don't know, its values end up in [`crate::Booking::columns`].
+    Unmapped { index: usize, label: String },
+    /// A column we expected, but which isn't part of the table.
+    ///
+    /// Without the ID, start, or end, the rows get skipped, see [`super::SkipReason::MissingValue`].
+    Missing { column: Column },
+}
+
+impl Display for ColumnWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoHeader => f.write_str("Missing header row, assuming the default layout"),
+            Self::Unmapped { index, label } => write!(f, "Unknown column #{index}: {label}"),
+            Self::Missing { column } => write!(f, "Missing column: {column}"),
+        }
+    }
+}
+
+/// The columns of a search result table, in order.
+#[derive(Clone, Debug)]
+pub(crate) struct Columns {
+    columns: Vec<(String, Option<Column>)>,
+}
+
+impl Default for Columns {
+    fn default() -> Self {
+        Self {
+            columns: Column::LEGACY
+                .iter()
+                .map(|column| (column.to_string(), Some(*column)))
+                .collect(),
+        }
+    }
+}
+
+impl Columns {
+    pub fn from_header<I, S>(labels: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            columns: labels
+                .into_iter()
+                .map(|label| {
+                    let label = label.into().trim().to_string();
+                    let column = Column::from_label(&label);
+                    (label, column)
+                })
+                .collect(),
+        }
+    }
+
+    /// Check if the table contains the column.
+    pub fn contains(&self, column: Column) -> bool {
+        self.columns.iter().any(|(_, c)| *c == Some(column))
+    }
+
+    /// Get the label and column at an index.
+    pub fn get(&self, index: usize) -> Option<(&str, Option<Column>)> {
+        self.columns
+            .get(index)
+            .map(|(label, column)| (label.as_str(), *column))
+    }
+
+    pub fn warnings(&self) -> Vec<ColumnWarning> {
+        let unmapped = self
+            .columns
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, column))| column.is_none())
+            .map(|(index, (label, _))| ColumnWarning::Unmapped {
+                index,
+                label: label.clone(),
+            });
+        let missing = Column::EXPECTED
+            .into_iter()
+            .filter(|column| !self.contains(*column))
+            .map(|column| ColumnWarning::Missing { column });
+
+        unmapped.chain(missing).collect()
+    }
+}
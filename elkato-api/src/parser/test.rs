@@ -0,0 +1,453 @@
+use super::*;
+use crate::BookingError;
+use chrono_tz::Europe::Berlin;
+
+fn data(name: &str) -> String {
+    let path = format!("{}/tests/data/{name}", env!("CARGO_MANIFEST_DIR"));
+    std::fs::read_to_string(&path).unwrap_or_else(|err| panic!("Failed to read {path}: {err}"))
+}
+
+fn fixture(name: &str) -> String {
+    data(&format!("search/{name}"))
+}
+
+fn ids(response: &ListResponse) -> Vec<&str> {
+    response.bookings.iter().map(|b| b.id.as_str()).collect()
+}
+
+fn assert_paging(paging: Option<Paging>, from: usize, to: usize, total: usize) {
+    let paging = paging.expect("Must have paging information");
+    assert_eq!(
+        (paging.from, paging.to, paging.total),
+        (from, to, total),
+        "Unexpected paging: {paging:?}"
+    );
+}
+
+#[test]
+fn paging_first_page() {
+    let result = parse_query(&fixture("first_page.html"), Berlin).unwrap();
+
+    assert_paging(result.paging, 1, 3, 7);
+    assert_eq!(ids(&result), vec!["1001", "1002", "1003"]);
+}
+
+#[test]
+fn paging_middle_page() {
+    let result = parse_query(&fixture("middle_page.html"), Berlin).unwrap();
+
+    assert_paging(result.paging, 4, 6, 7);
+    assert_eq!(ids(&result), vec!["1004", "1005", "1006"]);
+}
+
+#[test]
+fn paging_last_page() {
+    let result = parse_query(&fixture("last_page.html"), Berlin).unwrap();
+
+    assert_paging(result.paging, 7, 7, 7);
+    assert_eq!(ids(&result), vec!["1007"]);
+}
+
+#[test]
+fn paging_single_page() {
+    let result = parse_query(&fixture("single_page.html"), Berlin).unwrap();
+
+    assert_paging(result.paging, 1, 2, 2);
+    assert_eq!(ids(&result), vec!["1001", "1002"]);
+}
+
+#[test]
+fn paging_empty() {
+    let result = parse_query(&fixture("empty.html"), Berlin).unwrap();
+
+    assert!(result.paging.is_none());
+    assert!(result.bookings.is_empty());
+}
+
+#[test]
+fn details() {
+    let details = parse_details("1003", &data("view_entry/details.html"), Berlin).unwrap();
+
+    assert_eq!(details.booking.id, "1003");
+    assert_eq!(details.booking.resource, "Transporter");
+    assert_eq!(details.booking.owner, "demo");
+    assert_eq!(details.booking.booker, "admin");
+    assert_eq!(
+        details.booking.start.to_rfc3339(),
+        "2023-02-08T08:00:00+00:00"
+    );
+    assert_eq!(
+        details.booking.end.to_rfc3339(),
+        "2023-02-08T16:00:00+00:00"
+    );
+    assert_eq!(
+        details.booking.description,
+        "Umzug in die Gartenstraße, bitte Decken mitnehmen"
+    );
+    assert_eq!(details.creator.as_deref(), Some("admin"));
+    assert_eq!(
+        details.created.map(|d| d.to_rfc3339()).as_deref(),
+        Some("2023-02-01T18:42:10+00:00")
+    );
+    assert_eq!(
+        details.modified.map(|d| d.to_rfc3339()).as_deref(),
+        Some("2023-02-03T07:05:00+00:00")
+    );
+    assert_eq!(
+        details.fields,
+        vec![
+            ("Dauer".to_string(), "8:00".to_string()),
+            ("Kilometerstand".to_string(), "12345".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn resources() {
+    let resources = parse_resources(&fixture("form.html")).unwrap();
+
+    let resource = |id: &str, name: &str, group: Option<&str>| Resource {
+        id: id.to_string(),
+        name: name.to_string(),
+        group: group.map(ToString::to_string),
+    };
+
+    assert_eq!(
+        resources,
+        vec![
+            resource("3", "VW Golf", Some("PKW")),
+            resource("7", "Opel Corsa", Some("PKW")),
+            resource("12", "Transporter", Some("Transporter")),
+            resource("15", "Lastenrad", None),
+        ]
+    );
+}
+
+#[test]
+fn entry_response() {
+    assert_eq!(
+        parse_entry_response(&data("edit_entry/saved.html")).unwrap(),
+        "1501"
+    );
+    // the link to the conflicting booking is not the ID of the new one
+    assert!(matches!(
+        parse_entry_response(&data("edit_entry/conflict.html")),
+        Err(Error::Booking(BookingError::Conflict(_)))
+    ));
+    assert!(matches!(
+        parse_entry_response(&data("edit_entry/no_id.html")),
+        Err(Error::ParseError { .. })
+    ));
+}
+
+#[test]
+fn entry_id() {
+    for (href, expected) in [
+        ("view_entry.php?club=demo&id=1501", Some("1501")),
+        ("view_entry.php?id=1501&club=demo", Some("1501")),
+        ("/buchung/view_entry.php?club=demo&id=1501", Some("1501")),
+        (
+            "https://www.elkato.de/buchung/view_entry.php?club=Segel+%26+Surf&id=7",
+            Some("7"),
+        ),
+        ("view_entry.php?club=demo", None),
+        ("view_entry.php", None),
+        ("edit_entry.php?club=demo&id=1501", None),
+        ("search.php?club=demo&search_pos=3", None),
+    ] {
+        assert_eq!(entry_id_from_href(href).as_deref(), expected, "{href}");
+    }
+}
+
+#[test]
+fn modify_response() {
+    assert!(parse_modify_response(&data("modify/cancelled.html")).is_ok());
+
+    for (name, expected) in [
+        ("not_owner.html", BookingError::NotOwner),
+        ("not_allowed.html", BookingError::NotOwner),
+        ("already_started.html", BookingError::AlreadyStarted),
+        ("not_found.html", BookingError::NotFound),
+    ] {
+        match parse_modify_response(&data(&format!("modify/{name}"))) {
+            Err(Error::Booking(err)) => assert_eq!(err, expected, "{name}"),
+            result => panic!("{name}: unexpected result: {result:?}"),
+        }
+    }
+
+    match parse_modify_response(&data("modify/unknown.html")) {
+        Err(Error::ServerMessage(message)) => {
+            assert_eq!(message, "Fehler: Datenbank nicht erreichbar")
+        }
+        result => panic!("unexpected result: {result:?}"),
+    }
+}
+
+#[test]
+fn access() {
+    assert!(check_access(&fixture("first_page.html")).is_ok());
+    assert!(matches!(
+        check_access(&data("errors/login.html")),
+        Err(Error::Unauthorized)
+    ));
+    assert!(matches!(
+        check_access(&data("errors/unknown_club.html")),
+        Err(Error::UnknownClub)
+    ));
+}
+
+#[test]
+fn access_user_text() {
+    // phrases of error messages, as part of a booking description
+    let body = fixture("user_text.html");
+    assert!(check_access(&body).is_ok());
+
+    let result = parse_query(&body, Berlin).unwrap();
+    assert_eq!(ids(&result), vec!["1401", "1402"]);
+    assert!(result.bookings[0]
+        .description
+        .contains("Zugriff verweigert"));
+
+    // but the same phrase in the error block is an error
+    let body = body.replace(
+        "<h3>Suchergebnis</h3>",
+        r#"<p><font color="red">Zugriff verweigert</font></p>"#,
+    );
+    assert!(matches!(check_access(&body), Err(Error::Unauthorized)));
+}
+
+fn utc(datetime: DateTime<Utc>) -> String {
+    datetime.to_rfc3339()
+}
+
+#[test]
+fn dst_gap() {
+    // clocks jump from 02:00 to 03:00, 02:30 doesn't exist and gets shifted to 03:30 CEST
+    let (start, end) = to_range("26.03.23, 02:30", "26.03.23, 04:00", Berlin).unwrap();
+    assert_eq!(utc(start), "2023-03-26T01:30:00+00:00");
+    assert_eq!(utc(end), "2023-03-26T02:00:00+00:00");
+
+    // around the gap
+    let (start, end) = to_range("26.03.23, 01:30", "26.03.23, 03:30", Berlin).unwrap();
+    assert_eq!(utc(start), "2023-03-26T00:30:00+00:00");
+    assert_eq!(utc(end), "2023-03-26T01:30:00+00:00");
+}
+
+#[test]
+fn dst_overlap() {
+    // clocks fall back from 03:00 to 02:00, 02:30 occurs twice, prefer the first one
+    let (start, end) = to_range("29.10.23, 02:30", "29.10.23, 04:00", Berlin).unwrap();
+    assert_eq!(utc(start), "2023-10-29T00:30:00+00:00");
+    assert_eq!(utc(end), "2023-10-29T03:00:00+00:00");
+
+    // the end must come after the start, so it has to be the second occurrence
+    let (start, end) = to_range("29.10.23, 02:40", "29.10.23, 02:10", Berlin).unwrap();
+    assert_eq!(utc(start), "2023-10-29T00:40:00+00:00");
+    assert_eq!(utc(end), "2023-10-29T01:10:00+00:00");
+
+    // the first occurrence of the end already comes after the start
+    let (start, end) = to_range("29.10.23, 01:30", "29.10.23, 02:30", Berlin).unwrap();
+    assert_eq!(utc(start), "2023-10-28T23:30:00+00:00");
+    assert_eq!(utc(end), "2023-10-29T00:30:00+00:00");
+
+    // a booking spanning the whole night
+    let (start, end) = to_range("28.10.23, 20:00", "29.10.23, 08:00", Berlin).unwrap();
+    assert_eq!(utc(start), "2023-10-28T18:00:00+00:00");
+    assert_eq!(utc(end), "2023-10-29T07:00:00+00:00");
+}
+
+#[test]
+fn columns_default() {
+    let result = parse_query(&fixture("first_page.html"), Berlin).unwrap();
+
+    assert!(
+        result.report.warnings.is_empty(),
+        "{:?}",
+        result.report.warnings
+    );
+    let booking = &result.bookings[0];
+    assert_eq!(booking.resource, "VW Golf");
+    assert_eq!(booking.owner, "demo");
+    assert_eq!(booking.booker, "demo");
+    assert_eq!(booking.duration, Some(150));
+    assert_eq!(booking.description, "Einkauf");
+    assert!(booking.columns.is_empty());
+}
+
+#[test]
+fn columns_custom() {
+    let result = parse_query(&fixture("custom_columns.html"), Berlin).unwrap();
+
+    assert_eq!(
+        result.report.warnings,
+        vec![
+            ColumnWarning::Unmapped {
+                index: 8,
+                label: "Kostenstelle".into()
+            },
+            ColumnWarning::Missing {
+                column: Column::Booker
+            },
+        ]
+    );
+    assert_eq!(ids(&result), vec!["2001", "2002"]);
+
+    let booking = &result.bookings[0];
+    assert_eq!(booking.resource, "Lastenrad");
+    assert_eq!(booking.owner, "demo");
+    // without a booker column, it's the owner
+    assert_eq!(booking.booker, "demo");
+    assert_eq!(booking.start.to_rfc3339(), "2023-02-09T07:00:00+00:00");
+    assert_eq!(booking.end.to_rfc3339(), "2023-02-09T09:30:00+00:00");
+    assert_eq!(booking.duration, Some(150));
+    assert_eq!(booking.description, "Wochenmarkt");
+    assert_eq!(
+        booking.columns,
+        vec![("Kostenstelle".to_string(), "4711".to_string())]
+    );
+
+    assert_eq!(result.bookings[1].duration, Some(48 * 60));
+}
+
+#[test]
+fn columns_no_header() {
+    let result = parse_query(&fixture("no_header.html"), Berlin).unwrap();
+
+    assert_eq!(result.report.warnings, vec![ColumnWarning::NoHeader]);
+    assert_eq!(ids(&result), vec!["1001"]);
+    assert_eq!(result.bookings[0].booker, "admin");
+    assert_eq!(result.bookings[0].description, "Einkauf");
+}
+
+#[test]
+fn columns_missing_required() {
+    // the page still gets parsed, reporting why its rows were skipped
+    let result = parse_query(&fixture("missing_column.html"), Berlin).unwrap();
+
+    assert!(result.bookings.is_empty());
+    assert_paging(result.paging, 1, 1, 1);
+    assert!(result.report.warnings.contains(&ColumnWarning::Missing {
+        column: Column::End
+    }));
+    assert_eq!(
+        result.report.skipped,
+        vec![SkippedRow {
+            cells: vec![
+                "1001".into(),
+                "VW Golf".into(),
+                "demo".into(),
+                "Mo".into(),
+                "06.02.23, 08:00".into(),
+                "2:30".into(),
+            ],
+            reason: SkipReason::MissingValue {
+                column: Column::End
+            },
+        }]
+    );
+}
+
+#[test]
+fn report_skipped() {
+    let result = parse_query(&fixture("malformed.html"), Berlin).unwrap();
+
+    assert_eq!(ids(&result), vec!["1001"]);
+    assert!(result.report.warnings.is_empty());
+
+    let skipped = &result.report.skipped;
+    assert_eq!(skipped.len(), 3, "{skipped:?}");
+
+    assert_eq!(skipped[0].cells[0], "1002");
+    assert!(matches!(skipped[0].reason, SkipReason::InvalidDate(_)));
+
+    assert_eq!(
+        skipped[1].cells,
+        vec!["1003", "Transporter", "demo", "Mi", "08.02.23, 09:00"]
+    );
+    assert_eq!(
+        skipped[1].reason,
+        SkipReason::MissingValue {
+            column: Column::End
+        }
+    );
+
+    assert_eq!(
+        skipped[2].reason,
+        SkipReason::MissingValue { column: Column::Id }
+    );
+}
+
+#[test]
+fn report_empty() {
+    for name in ["first_page.html", "empty.html"] {
+        let result = parse_query(&fixture(name), Berlin).unwrap();
+        assert!(result.report.is_empty(), "{name}: {:?}", result.report);
+    }
+}
+
+/// Snapshot the parsed result of each synthetic search result page.
+#[test]
+fn snapshots() {
+    for name in [
+        "empty",
+        "single_page",
+        "first_page",
+        "middle_page",
+        "last_page",
+        "multi_day",
+        "inactive",
+        "entities",
+        "custom_columns",
+        "no_header",
+        "malformed",
+    ] {
+        let result = parse_query(&fixture(&format!("{name}.html")), Berlin).unwrap();
+        insta::assert_yaml_snapshot!(name, result);
+    }
+}
+
+#[test]
+fn entities() {
+    let result = parse_query(&fixture("entities.html"), Berlin).unwrap();
+
+    let booking = &result.bookings[0];
+    assert_eq!(booking.resource, r#"Kleinbus "Groß""#);
+    assert_eq!(booking.owner, "jürgen");
+    assert_eq!(booking.description, "Fahrt nach München & zurück");
+
+    assert_eq!(
+        result.bookings[1].description,
+        "<Getränke> für\u{a0}die Feier"
+    );
+    // leading and trailing whitespace, including non-breaking spaces, is removed
+    assert_eq!(
+        result.bookings[2].description,
+        "Ärger mit der Kupplung, siehe Fahrtenbuch"
+    );
+}
+
+#[test]
+fn inactive() {
+    let result = parse_query(&fixture("inactive.html"), Berlin).unwrap();
+    assert_eq!(result.bookings.len(), 2);
+    assert!(result.bookings.iter().all(|b| b.cancelled));
+
+    let result = parse_query(&fixture("single_page.html"), Berlin).unwrap();
+    assert!(!result.bookings.is_empty());
+    assert!(result.bookings.iter().all(|b| !b.cancelled));
+}
+
+/// Pages captured from Elkato, using the `capture` example, must parse without any problems.
+#[test]
+fn captured() {
+    let dir = format!("{}/tests/data/search", env!("CARGO_MANIFEST_DIR"));
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let name = entry.unwrap().file_name().into_string().unwrap();
+        if !name.starts_with("captured_") {
+            continue;
+        }
+
+        let result = parse_query(&fixture(&name), Berlin).unwrap();
+        assert!(result.report.is_empty(), "{name}: {:?}", result.report);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::Credentials;
-use chrono::{Datelike, NaiveDate};
+use chrono::{DateTime, Datelike, NaiveDate, Timelike, Utc};
+use chrono_tz::Tz;
 use url::{ParseError, Url};
 
 pub(crate) fn date_filter_to_query(prefix: &str, date: Option<NaiveDate>) -> Vec<(String, String)> {
@@ -16,7 +17,7 @@ pub(crate) fn date_filter_to_query(prefix: &str, date: Option<NaiveDate>) -> Vec
 
 /// Create the URL for a booking
 pub(crate) fn make_url(id: &str, url: &Url, credentials: &Credentials) -> Result<Url, ParseError> {
-    let mut url = url.join(&format!("/buchung/view_entry.php"))?;
+    let mut url = url.join("/buchung/view_entry.php")?;
 
     url.query_pairs_mut()
         .clear()
@@ -25,3 +26,19 @@ pub(crate) fn make_url(id: &str, url: &Url, credentials: &Credentials) -> Result
 
     Ok(url)
 }
+
+/// Convert a point in time into the fields of the booking entry form.
+pub(crate) fn datetime_to_form(
+    prefix: &str,
+    datetime: &DateTime<Utc>,
+    tz: Tz,
+) -> Vec<(String, String)> {
+    let local = datetime.with_timezone(&tz);
+    vec![
+        (format!("{}_day", prefix), local.day().to_string()),
+        (format!("{}_month", prefix), local.month().to_string()),
+        (format!("{}_year", prefix), local.year().to_string()),
+        (format!("{}_hour", prefix), local.hour().to_string()),
+        (format!("{}_minute", prefix), local.minute().to_string()),
+    ]
+}
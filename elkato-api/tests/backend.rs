@@ -0,0 +1,190 @@
+//! Running the same scenario against Elkato, in the form of the mock server, and the in-memory
+//! backend.
+
+use chrono::{Duration, TimeZone, Utc};
+use elkato_api::{
+    availability::Interval,
+    backend::{BookingBackend, MemoryBackend},
+    cors::CorsProxy,
+    Booking, BookingError, Error, ListOptions, NewBooking, Resource,
+};
+use elkato_mock::{at, today, Data, MockServer};
+
+/// A backend for the user "demo", populated with the seeded mock data.
+fn memory(data: &Data) -> MemoryBackend {
+    let tz = data.timezone;
+    let backend =
+        MemoryBackend::new("demo").with_resources(data.resources.iter().map(|r| Resource {
+            id: r.id.clone(),
+            name: r.name.clone(),
+            group: r.group.clone(),
+        }));
+
+    for b in &data.bookings {
+        let utc = |local| {
+            tz.from_local_datetime(&local)
+                .earliest()
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        backend.add_booking(
+            &b.resource,
+            Booking {
+                id: b.id.to_string(),
+                resource: b.resource.clone(),
+                owner: b.owner.clone(),
+                booker: b.booker.clone(),
+                start: utc(b.start),
+                end: utc(b.end),
+                duration: None,
+                description: b.description.clone(),
+                columns: vec![],
+                location: None,
+                cancelled: !b.active,
+            },
+        );
+    }
+
+    backend
+}
+
+fn ids(bookings: &[Booking]) -> Vec<&str> {
+    bookings.iter().map(|b| b.id.as_str()).collect()
+}
+
+async fn scenario(backend: &dyn BookingBackend, data: &Data) {
+    backend.verify_credentials().await.unwrap();
+
+    let resources = backend.list_resources().await.unwrap();
+    assert_eq!(resources.len(), data.resources.len());
+
+    let all = backend.list_bookings(Default::default()).await.unwrap();
+    assert_eq!(all.len(), data.bookings.iter().filter(|b| b.active).count());
+
+    let mine = backend
+        .list_bookings(ListOptions {
+            owner: Some("demo".into()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(!mine.is_empty());
+    assert!(mine.iter().all(|b| b.owner == "demo"));
+
+    let details = backend.get_booking(&all[0].id).await.unwrap();
+    assert_eq!(details.booking.id, all[0].id);
+    assert!(matches!(
+        backend.get_booking("4711").await,
+        Err(Error::Booking(BookingError::NotFound))
+    ));
+
+    let new = NewBooking {
+        resource: "15".into(),
+        start: at(10, 9),
+        end: at(10, 11),
+        description: "Test".into(),
+        owner: None,
+    };
+    let created = backend.create_booking(new.clone()).await.unwrap();
+    assert_eq!(created.owner, "demo");
+
+    let conflicts = backend
+        .check_conflicts("15", Interval::new(at(10, 10), at(10, 12)))
+        .await
+        .unwrap();
+    assert_eq!(
+        conflicts
+            .iter()
+            .map(|c| c.booking.id.as_str())
+            .collect::<Vec<_>>(),
+        vec![created.id.as_str()]
+    );
+    assert!(matches!(
+        backend
+            .create_booking(NewBooking {
+                start: at(10, 10),
+                end: at(10, 12),
+                ..new
+            })
+            .await,
+        Err(Error::Booking(BookingError::Conflict(_)))
+    ));
+
+    let other = data
+        .bookings
+        .iter()
+        .find(|b| b.owner == "jürgen" && b.start.date() > today())
+        .unwrap();
+    assert!(matches!(
+        backend.cancel_booking(&other.id.to_string()).await,
+        Err(Error::Booking(BookingError::NotOwner))
+    ));
+
+    backend.cancel_booking(&created.id).await.unwrap();
+    let after = backend.list_bookings(Default::default()).await.unwrap();
+    assert_eq!(ids(&after), ids(&all));
+}
+
+#[tokio::test]
+async fn elkato() {
+    let server = MockServer::seeded().await.unwrap();
+    let api = server.api(CorsProxy::None, "demo");
+
+    scenario(&api, &server.data()).await;
+}
+
+#[tokio::test]
+async fn in_memory() {
+    let data = Data::seeded(today());
+    let backend = memory(&data);
+
+    scenario(&backend, &data).await;
+
+    // the cancelled booking is kept as inactive
+    assert_eq!(
+        backend.bookings().iter().filter(|b| b.cancelled).count(),
+        data.bookings.iter().filter(|b| !b.active).count() + 1
+    );
+}
+
+#[tokio::test]
+async fn demo() {
+    let now = Utc::now();
+    let backend = elkato_api::backend::demo("demo", now, chrono_tz::Europe::Berlin);
+
+    let resources = backend.list_resources().await.unwrap();
+    let mine = backend
+        .list_bookings(ListOptions {
+            owner: Some("demo".into()),
+            start_from: Some(today() - Duration::days(7)),
+            end_to: Some(today() + Duration::days(7)),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert!(mine.iter().any(|b| b.is_past(&now)));
+    assert!(mine.iter().any(|b| b.is_active(&now)));
+    assert!(mine.iter().filter(|b| b.start > now).count() > 1);
+    assert!(mine.iter().any(|b| b.booker != b.owner));
+
+    // the sample data follows the rules of Elkato
+    for resource in resources {
+        let bookings = backend
+            .list_bookings(ListOptions {
+                resource: Some(resource.id.clone()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        for b in &bookings {
+            assert!(b.start < b.end);
+            assert_eq!(b.resource, resource.name);
+            let conflicts = backend
+                .check_conflicts(&resource.id, Interval::new(b.start, b.end))
+                .await
+                .unwrap();
+            assert_eq!(conflicts.len(), 1, "Overlapping bookings: {conflicts:?}");
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! Checking the requests the CORS proxy variants send, using a local echo server.
+
+use elkato_api::cors::CorsProxy;
+use std::net::SocketAddr;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+    task::JoinHandle,
+};
+use url::Url;
+
+/// The parameters of a search, including some which need encoding.
+const SEARCH: &[(&str, &str)] = &[
+    ("club", "Segel & Surf"),
+    ("search_pos", "0"),
+    ("sel_room", "all"),
+    ("sel_owner", "jürgen"),
+    ("active", "1"),
+    ("s_from_day", "1"),
+    ("s_from_month", "3"),
+    ("s_from_year", "2023"),
+    ("e_to_day", "31"),
+    ("e_to_month", "3"),
+    ("e_to_year", "2023"),
+];
+
+/// A request, as received by the echo server.
+#[derive(Debug)]
+struct Echo {
+    /// The request target, the path and query.
+    target: String,
+    /// The headers, with lowercase names.
+    headers: Vec<(String, String)>,
+}
+
+impl Echo {
+    fn url(&self) -> Url {
+        Url::parse("http://echo")
+            .unwrap()
+            .join(&self.target)
+            .unwrap()
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// A server responding with the request line and headers it received.
+async fn echo_server() -> (Url, JoinHandle<()>) {
+    let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))
+        .await
+        .unwrap();
+    let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+
+    let handle = tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let mut stream = BufReader::new(stream);
+            let mut head = String::new();
+            loop {
+                let mut line = String::new();
+                if stream.read_line(&mut line).await.unwrap_or(0) == 0 || line == "\r\n" {
+                    break;
+                }
+                head.push_str(&line);
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{head}",
+                head.len()
+            );
+            let _ = stream.get_mut().write_all(response.as_bytes()).await;
+        }
+    });
+
+    (url, handle)
+}
+
+async fn send(proxy: &CorsProxy, url: Url) -> Echo {
+    let client = reqwest::Client::new();
+    let builder = client
+        .get(url)
+        .query(SEARCH)
+        .basic_auth("demo", Some("demo"));
+
+    let body = proxy
+        .send(&client, builder)
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    let mut lines = body.lines();
+    let target = lines.next().unwrap().split(' ').nth(1).unwrap().to_string();
+    let headers = lines
+        .filter_map(|line| line.split_once(": "))
+        .map(|(name, value)| (name.to_lowercase(), value.to_string()))
+        .collect();
+
+    Echo { target, headers }
+}
+
+fn search_url() -> Url {
+    let mut url = Url::parse("https://www.elkato.de/buchung/search.php").unwrap();
+    url.query_pairs_mut().extend_pairs(SEARCH);
+    url
+}
+
+#[tokio::test]
+async fn none() {
+    let (server, handle) = echo_server().await;
+
+    let echo = send(&CorsProxy::None, server.join("buchung/search.php").unwrap()).await;
+    assert_eq!(echo.url().path(), "/buchung/search.php");
+    assert_eq!(echo.url().query(), search_url().query());
+    assert!(echo.header("authorization").is_some());
+
+    handle.abort();
+}
+
+#[tokio::test]
+async fn prepend() {
+    let (server, handle) = echo_server().await;
+    let target = search_url();
+
+    for (base, prefix) in [
+        ("", "/"),
+        ("cors", "/cors/"),
+        ("cors/", "/cors/"),
+        ("some/cors/", "/some/cors/"),
+    ] {
+        let proxy = CorsProxy::Prepend(server.join(base).unwrap());
+        let echo = send(
+            &proxy,
+            Url::parse("https://www.elkato.de/buchung/search.php").unwrap(),
+        )
+        .await;
+
+        assert_eq!(
+            echo.target,
+            format!("{prefix}{target}"),
+            "Base path: {base}"
+        );
+
+        // what a proxy would extract
+        let (path, query) = echo.target.split_once('?').unwrap();
+        let forwarded =
+            Url::parse(&format!("{}?{query}", path.strip_prefix(prefix).unwrap())).unwrap();
+        assert_eq!(forwarded, target, "Base path: {base}");
+        assert!(echo.header("authorization").is_some());
+    }
+
+    handle.abort();
+}
+
+#[tokio::test]
+async fn prepend_with_query() {
+    let (server, handle) = echo_server().await;
+    let target = search_url();
+
+    let proxy = CorsProxy::Prepend(server.join("cors/?key=value").unwrap());
+    let echo = send(
+        &proxy,
+        Url::parse("https://www.elkato.de/buchung/search.php").unwrap(),
+    )
+    .await;
+
+    // the query of the proxy is kept, followed by the one of the target
+    assert_eq!(
+        echo.target,
+        format!(
+            "/cors/{}?key=value&{}",
+            &target[..url::Position::AfterPath],
+            target.query().unwrap()
+        )
+    );
+
+    handle.abort();
+}
+
+#[tokio::test]
+async fn prepend_without_query() {
+    let (server, handle) = echo_server().await;
+
+    let proxy = CorsProxy::Prepend(server.join("cors/").unwrap());
+    let client = reqwest::Client::new();
+    let body = proxy
+        .send(
+            &client,
+            client.get("http://localhost:8081/buchung/view_entry.php"),
+        )
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    assert!(
+        body.starts_with("GET /cors/http://localhost:8081/buchung/view_entry.php HTTP/1.1"),
+        "{body}"
+    );
+
+    handle.abort();
+}
+
+#[tokio::test]
+async fn query() {
+    let (server, handle) = echo_server().await;
+    let target = search_url();
+
+    for base in ["proxy.php", "proxy.php?key=value"] {
+        let proxy = CorsProxy::Query {
+            url: server.join(base).unwrap(),
+            parameter: "url".into(),
+        };
+        let echo = send(
+            &proxy,
+            Url::parse("https://www.elkato.de/buchung/search.php").unwrap(),
+        )
+        .await;
+
+        let url = echo.url();
+        assert_eq!(url.path(), "/proxy.php");
+        let params: Vec<_> = url.query_pairs().into_owned().collect();
+        let forwarded = params
+            .iter()
+            .find(|(name, _)| name == "url")
+            .map(|(_, value)| Url::parse(value).unwrap());
+        assert_eq!(forwarded, Some(target.clone()), "Base: {base}");
+        if base.contains("key") {
+            assert!(params.contains(&("key".into(), "value".into())));
+        }
+        assert!(echo.header("authorization").is_some());
+    }
+
+    handle.abort();
+}
+
+#[tokio::test]
+async fn authorization_header() {
+    let (server, handle) = echo_server().await;
+
+    let proxy = CorsProxy::Prepend(server.clone()).with_authorization_header();
+    let echo = send(
+        &proxy,
+        Url::parse("https://www.elkato.de/buchung/search.php").unwrap(),
+    )
+    .await;
+
+    assert_eq!(echo.target, format!("/{}", search_url()));
+    assert_eq!(echo.header("authorization"), None);
+    assert_eq!(
+        echo.header("x-proxy-authorization"),
+        Some("Basic ZGVtbzpkZW1v")
+    );
+
+    handle.abort();
+}
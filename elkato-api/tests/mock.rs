@@ -0,0 +1,579 @@
+//! End-to-end tests of the API, against the mock server.
+
+use chrono::Duration;
+use elkato_api::{
+    cors::CorsProxy, Api, BookingChanges, BookingError, BookingState, Credentials, Error,
+    ListOptions, NewBooking,
+};
+use elkato_mock::{at, today, Data, MockServer};
+use futures::{StreamExt, TryStreamExt};
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+async fn server() -> MockServer {
+    let mut data = Data::seeded(today());
+    // force multiple pages
+    data.page_size = 3;
+    MockServer::start(data).await.unwrap()
+}
+
+fn proxies(server: &MockServer) -> Vec<CorsProxy> {
+    vec![
+        CorsProxy::None,
+        CorsProxy::Prepend(server.prepend_proxy_url()),
+        CorsProxy::Query {
+            url: server.query_proxy_url(),
+            parameter: "url".into(),
+        },
+    ]
+}
+
+async fn list(api: &Api, options: ListOptions) -> Result<Vec<String>, Error> {
+    api.list_bookings(options)
+        .map(|b| b.map(|b| b.id))
+        .boxed_local()
+        .try_collect()
+        .await
+}
+
+/// The IDs of the bookings in the mock server, matching the filter, ordered by start.
+fn expected<F>(server: &MockServer, f: F) -> Vec<String>
+where
+    F: Fn(&elkato_mock::data::Booking) -> bool,
+{
+    let mut bookings: Vec<_> = server
+        .data()
+        .bookings
+        .into_iter()
+        .filter(|b| f(b))
+        .collect();
+    bookings.sort_by_key(|b| (b.start, b.id));
+    bookings.into_iter().map(|b| b.id.to_string()).collect()
+}
+
+#[tokio::test]
+async fn list_all_proxies() {
+    let server = server().await;
+    let expected = expected(&server, |b| b.active);
+    assert!(expected.len() > 3, "Must span multiple pages");
+
+    for proxy in proxies(&server) {
+        let api = server.api(proxy.clone(), "demo");
+        assert_eq!(
+            list(&api, Default::default()).await.unwrap(),
+            expected,
+            "Proxy: {proxy:?}"
+        );
+    }
+}
+
+/// Elkato might redirect to the entry after saving it, which proxies follow on their own.
+#[tokio::test]
+async fn create_redirected() {
+    let server = server().await;
+    server.with_data(|data| data.redirect = true);
+
+    for (days, proxy) in (20..).zip(proxies(&server)) {
+        let booking = server
+            .api(proxy.clone(), "demo")
+            .create_booking(NewBooking {
+                resource: "15".into(),
+                start: at(days, 9),
+                end: at(days, 11),
+                description: format!("Redirected {days}"),
+                owner: None,
+            })
+            .await
+            .unwrap();
+
+        let created = server.data().booking(booking.id.parse().unwrap()).cloned();
+        assert_eq!(
+            created.map(|b| b.description),
+            Some(format!("Redirected {days}")),
+            "Proxy: {proxy:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn list_filtered() {
+    let server = server().await;
+    let api = server.api(CorsProxy::None, "demo");
+
+    assert_eq!(
+        list(
+            &api,
+            ListOptions {
+                owner: Some("demo".into()),
+                resource: Some("3".into()),
+                ..Default::default()
+            }
+        )
+        .await
+        .unwrap(),
+        expected(&server, |b| b.active
+            && b.owner == "demo"
+            && b.resource == "3")
+    );
+
+    assert_eq!(
+        list(
+            &api,
+            ListOptions {
+                start_from: Some(today()),
+                end_to: Some(today() + Duration::days(4)),
+                ..Default::default()
+            }
+        )
+        .await
+        .unwrap(),
+        expected(&server, |b| b.active
+            && b.start.date() >= today()
+            && b.end.date() <= today() + Duration::days(4))
+    );
+
+    assert_eq!(
+        list(
+            &api,
+            ListOptions {
+                state: BookingState::Inactive,
+                ..Default::default()
+            }
+        )
+        .await
+        .unwrap(),
+        expected(&server, |b| !b.active)
+    );
+
+    // inactive bookings are reported as cancelled
+    let all: Vec<_> = api
+        .list_bookings(ListOptions {
+            state: BookingState::All,
+            ..Default::default()
+        })
+        .boxed_local()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(
+        all.iter()
+            .filter(|b| b.cancelled)
+            .map(|b| b.id.clone())
+            .collect::<Vec<_>>(),
+        expected(&server, |b| !b.active)
+    );
+}
+
+#[tokio::test]
+async fn list_diagnostics() {
+    let server = server().await;
+
+    let (bookings, report) = server
+        .api(CorsProxy::None, "demo")
+        .list_bookings_with_diagnostics(Default::default())
+        .await
+        .unwrap();
+
+    assert_eq!(bookings.len(), expected(&server, |b| b.active).len());
+    assert!(report.is_empty(), "{report:?}");
+}
+
+#[tokio::test]
+async fn list_empty() {
+    let server = server().await;
+    server.with_data(|data| data.bookings.clear());
+
+    assert!(
+        list(&server.api(CorsProxy::None, "demo"), Default::default())
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn resources() {
+    let server = server().await;
+
+    let resources = server
+        .api(CorsProxy::None, "demo")
+        .list_resources()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        resources
+            .iter()
+            .map(|r| (r.id.as_str(), r.name.as_str(), r.group.as_deref()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("3", "VW Golf", Some("PKW")),
+            ("7", "Opel Corsa", Some("PKW")),
+            ("12", "Transporter", Some("Transporter")),
+            ("15", "Lastenrad", None),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn get_booking() {
+    let server = server().await;
+    let api = server.api(CorsProxy::None, "demo");
+
+    let expected = server
+        .data()
+        .bookings
+        .into_iter()
+        .find(|b| b.booker != b.owner)
+        .unwrap();
+
+    let details = api.get_booking(&expected.id.to_string()).await.unwrap();
+    assert_eq!(details.booking.resource, "Opel Corsa");
+    assert_eq!(details.booking.owner, expected.owner);
+    assert_eq!(details.booking.booker, expected.booker);
+    assert_eq!(details.booking.description, expected.description);
+    assert!(details.booking.location.is_some());
+
+    assert!(matches!(
+        api.get_booking("4711").await,
+        Err(Error::Booking(BookingError::NotFound))
+    ));
+}
+
+#[tokio::test]
+async fn booking_lifecycle() {
+    let server = server().await;
+    let api = server.api(CorsProxy::None, "demo");
+
+    let new = NewBooking {
+        resource: "15".into(),
+        start: at(10, 9),
+        end: at(10, 11),
+        description: "Test".into(),
+        owner: None,
+    };
+
+    let booking = api.create_booking(new.clone()).await.unwrap();
+    let id = booking.id.clone();
+    let created = server.data().booking(id.parse().unwrap()).cloned().unwrap();
+    assert_eq!(created.owner, "demo");
+    assert_eq!(created.description, "Test");
+
+    // the same as a search returns it
+    let listed = api
+        .list_bookings(ListOptions {
+            resource: Some("15".into()),
+            ..Default::default()
+        })
+        .boxed_local()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|b| b.id == id)
+        .unwrap();
+    assert_eq!(booking.resource, "Lastenrad");
+    assert_eq!(
+        (&booking.resource, &booking.owner, &booking.booker),
+        (&listed.resource, &listed.owner, &listed.booker)
+    );
+    assert_eq!((booking.start, booking.end), (listed.start, listed.end));
+    assert_eq!(booking.location, listed.location);
+
+    // booking the same slot again conflicts
+    assert!(matches!(
+        api.create_booking(NewBooking {
+            start: at(10, 10),
+            end: at(10, 12),
+            ..new.clone()
+        })
+        .await,
+        Err(Error::Booking(BookingError::Conflict(_)))
+    ));
+    assert!(!api
+        .check_conflicts("15", booking_interval(&new))
+        .await
+        .unwrap()
+        .is_empty());
+
+    let updated = api
+        .update_booking(
+            &id,
+            BookingChanges {
+                end: Some(at(10, 12)),
+                description: Some("Geändert".into()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(updated.id, id);
+    assert_eq!(updated.resource, "Lastenrad");
+    assert_eq!(updated.start, at(10, 9));
+    assert_eq!(updated.end, at(10, 12));
+    let details = api.get_booking(&id).await.unwrap();
+    assert_eq!(details.booking.description, "Geändert");
+    assert_eq!(details.booking.end, at(10, 12));
+
+    api.cancel_booking(&id).await.unwrap();
+    assert!(!server.data().booking(id.parse().unwrap()).unwrap().active);
+
+    api.delete_booking(&id).await.unwrap();
+    assert!(server.data().booking(id.parse().unwrap()).is_none());
+    assert!(matches!(
+        api.delete_booking(&id).await,
+        Err(Error::Booking(BookingError::NotFound))
+    ));
+}
+
+fn booking_interval(booking: &NewBooking) -> elkato_api::availability::Interval {
+    elkato_api::availability::Interval::new(booking.start, booking.end)
+}
+
+#[tokio::test]
+async fn booking_rejected() {
+    let server = server().await;
+    let api = server.api(CorsProxy::None, "demo");
+
+    let data = server.data();
+    let other = data
+        .bookings
+        .iter()
+        .find(|b| b.owner == "jürgen" && b.start.date() > today())
+        .unwrap();
+    let started = data
+        .bookings
+        .iter()
+        .find(|b| b.owner == "demo" && b.end.date() < today())
+        .unwrap();
+
+    assert!(matches!(
+        api.cancel_booking(&other.id.to_string()).await,
+        Err(Error::Booking(BookingError::NotOwner))
+    ));
+    assert!(matches!(
+        api.cancel_booking(&started.id.to_string()).await,
+        Err(Error::Booking(BookingError::AlreadyStarted))
+    ));
+}
+
+#[tokio::test]
+async fn unauthorized() {
+    let server = server().await;
+
+    // Elkato renders the login form, or redirects to the login page, which only the URL tells
+    for redirect in [false, true] {
+        server.with_data(|data| data.redirect = redirect);
+
+        for proxy in proxies(&server) {
+            let api = server.api(proxy.clone(), "wrong");
+            assert!(
+                matches!(api.verify_credentials().await, Err(Error::Unauthorized)),
+                "Proxy: {proxy:?}, redirect: {redirect}"
+            );
+            assert!(
+                matches!(
+                    list(&api, Default::default()).await,
+                    Err(Error::Unauthorized)
+                ),
+                "Proxy: {proxy:?}, redirect: {redirect}"
+            );
+        }
+    }
+
+    let api = server.api(CorsProxy::None, "demo");
+    assert!(api.verify_credentials().await.is_ok());
+}
+
+#[tokio::test]
+async fn unknown_club() {
+    let server = server().await;
+
+    let api = Api::new(
+        server.url(),
+        CorsProxy::None,
+        Credentials {
+            username: "demo".into(),
+            password: "demo".into(),
+            club: "unknown".into(),
+        },
+    )
+    .unwrap();
+
+    assert!(matches!(
+        api.verify_credentials().await,
+        Err(Error::UnknownClub)
+    ));
+}
+
+#[tokio::test]
+async fn server_error() {
+    let server = server().await;
+
+    for proxy in proxies(&server) {
+        let api = server.api(proxy.clone(), "demo");
+
+        server.fail_with(Some(503));
+        let err = list(&api, Default::default()).await.unwrap_err();
+        assert!(
+            matches!(err, Error::HttpStatus(status) if status.as_u16() == 503),
+            "Proxy: {proxy:?}, error: {err}"
+        );
+        assert!(err.is_transient());
+
+        server.fail_with(None);
+        assert!(list(&api, Default::default()).await.is_ok());
+    }
+}
+
+#[tokio::test]
+async fn unreachable() {
+    let server = server().await;
+    let url = server.url();
+    drop(server);
+
+    let api = Api::new(
+        url,
+        CorsProxy::None,
+        Credentials {
+            username: "demo".into(),
+            password: "demo".into(),
+            club: "demo".into(),
+        },
+    )
+    .unwrap();
+
+    let err = api.verify_credentials().await.unwrap_err();
+    assert!(matches!(err, Error::ProxyFailure(_)), "{err}");
+    assert!(err.is_transient());
+}
+
+/// A proxy which fails all requests with a 503.
+async fn broken_proxy() -> (url::Url, Arc<AtomicUsize>, tokio::task::JoinHandle<()>) {
+    let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))
+        .await
+        .unwrap();
+    let url = url::Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+    let requests = Arc::new(AtomicUsize::new(0));
+
+    let handle = tokio::spawn({
+        let requests = requests.clone();
+        async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                requests.fetch_add(1, Ordering::SeqCst);
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf).await;
+                let _ = stream
+                    .write_all(
+                        b"HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                    )
+                    .await;
+            }
+        }
+    });
+
+    (url, requests, handle)
+}
+
+#[tokio::test]
+async fn fallback() {
+    let server = server().await;
+    let expected = expected(&server, |b| b.active);
+
+    let (broken, requests, handle) = broken_proxy().await;
+    let unreachable = {
+        let server = MockServer::start(Data::default()).await.unwrap();
+        server.prepend_proxy_url()
+    };
+    let working = CorsProxy::Query {
+        url: server.query_proxy_url(),
+        parameter: "url".into(),
+    };
+
+    let api = server.api(
+        CorsProxy::fallback(vec![
+            CorsProxy::Prepend(unreachable.clone()),
+            CorsProxy::Prepend(broken.clone()),
+            working.clone(),
+        ]),
+        "demo",
+    );
+    // starts with the first one
+    assert_eq!(api.proxy().to_string(), unreachable.to_string());
+
+    assert_eq!(list(&api, Default::default()).await.unwrap(), expected);
+    assert_eq!(
+        api.proxy().to_string(),
+        server.query_proxy_url().to_string()
+    );
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    // goes straight to the proxy which worked
+    assert_eq!(list(&api, Default::default()).await.unwrap(), expected);
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    // sticks to the working proxy, and still reports errors of Elkato
+    assert!(api.verify_credentials().await.is_ok());
+    assert!(matches!(
+        server
+            .api(CorsProxy::fallback(vec![working.clone()]), "wrong")
+            .verify_credentials()
+            .await,
+        Err(Error::Unauthorized)
+    ));
+
+    // a failed deletion might have reached Elkato, so it isn't repeated on the next proxy
+    let id = server
+        .data()
+        .bookings
+        .iter()
+        .find(|b| b.active && b.owner == "demo" && b.start.date() > today())
+        .unwrap()
+        .id
+        .to_string();
+    let api = server.api(
+        CorsProxy::fallback(vec![CorsProxy::Prepend(broken.clone()), working.clone()]),
+        "demo",
+    );
+    let err = api.delete_booking(&id).await.unwrap_err();
+    assert!(
+        matches!(err, Error::HttpStatus(status) if status.as_u16() == 503),
+        "{err}"
+    );
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+    assert_eq!(api.proxy().to_string(), broken.to_string());
+    assert!(server.data().booking(id.parse().unwrap()).is_some());
+
+    // unless it never reached the proxy
+    let api = server.api(
+        CorsProxy::fallback(vec![
+            CorsProxy::Prepend(unreachable.clone()),
+            working.clone(),
+        ]),
+        "demo",
+    );
+    api.delete_booking(&id).await.unwrap();
+    assert!(server.data().booking(id.parse().unwrap()).is_none());
+
+    // fails with the error of the last proxy, if none works
+    let api = server.api(
+        CorsProxy::fallback(vec![CorsProxy::Prepend(broken)]),
+        "demo",
+    );
+    let err = api.verify_credentials().await.unwrap_err();
+    assert!(
+        matches!(err, Error::HttpStatus(status) if status.as_u16() == 503),
+        "{err}"
+    );
+
+    handle.abort();
+}
@@ -0,0 +1,103 @@
+//! Recording a session against the mock server, and replaying it without one.
+
+use elkato_api::{
+    cors::CorsProxy,
+    record::{Cassette, Recorder, REDACTED},
+    Api, Error,
+};
+use elkato_mock::{today, Data, MockServer};
+use futures::{StreamExt, TryStreamExt};
+
+const PASSWORD: &str = "geheim&sicher";
+
+async fn session(api: &Api) -> Result<(Vec<String>, String), Error> {
+    let ids: Vec<String> = api
+        .list_bookings(Default::default())
+        .map(|b| b.map(|b| b.id))
+        .boxed_local()
+        .try_collect()
+        .await?;
+    let details = api.get_booking(&ids[0]).await?;
+    Ok((ids, details.booking.description))
+}
+
+#[tokio::test]
+async fn record_and_replay() {
+    let mut data = Data::seeded(today());
+    data.page_size = 3;
+    for user in &mut data.users {
+        if user.username == "demo" {
+            user.password = PASSWORD.into();
+        }
+    }
+    let server = MockServer::start(data).await.unwrap();
+
+    let recorder = Recorder::new();
+    let recorded = session(
+        &server
+            .api(CorsProxy::None, PASSWORD)
+            .with_recorder(recorder.clone()),
+    )
+    .await
+    .unwrap();
+
+    let json = recorder.cassette().to_json().unwrap();
+    assert!(!json.contains(PASSWORD), "{json}");
+    assert!(!json.contains("geheim%26sicher"), "{json}");
+
+    let api = server.api(CorsProxy::None, PASSWORD);
+    drop(server);
+
+    let path = std::env::temp_dir().join(format!("elkato-cassette-{}.json", std::process::id()));
+    recorder.cassette().save(&path).unwrap();
+    let cassette = Cassette::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(cassette, Cassette::from_json(&json).unwrap());
+    assert!(cassette.interactions.len() > 2);
+
+    let api = api.with_replay(cassette);
+    let replayed = session(&api).await.unwrap();
+    assert_eq!(replayed, recorded);
+
+    assert!(matches!(
+        api.get_booking("4711").await,
+        Err(Error::NotRecorded(_))
+    ));
+}
+
+/// The demo account uses the same value for the user, the club, and the password.
+#[tokio::test]
+async fn password_like_other_values() {
+    let mut data = Data::seeded(today());
+    data.page_size = 3;
+    let server = MockServer::start(data).await.unwrap();
+
+    let recorder = Recorder::new();
+    let recorded = session(
+        &server
+            .api(CorsProxy::None, "demo")
+            .with_recorder(recorder.clone()),
+    )
+    .await
+    .unwrap();
+
+    let cassette = recorder.cassette();
+    let json = cassette.to_json().unwrap();
+    // only credentials get redacted, not every occurrence of the password
+    assert!(!json.contains(REDACTED), "{json}");
+    assert!(cassette.interactions.iter().all(|i| i
+        .request
+        .url
+        .query_pairs()
+        .any(|(k, v)| k == "club" && v == "demo")));
+    assert!(cassette
+        .interactions
+        .iter()
+        .any(|i| i.response.body.contains("demo")));
+
+    let api = server.api(CorsProxy::None, "demo");
+    drop(server);
+
+    let replayed = session(&api.with_replay(cassette)).await.unwrap();
+    assert_eq!(replayed, recorded);
+}
@@ -0,0 +1,173 @@
+//! The data served by the mock server.
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use chrono_tz::Tz;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct User {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Resource {
+    pub id: String,
+    pub name: String,
+    pub group: Option<String>,
+}
+
+/// A booking, with start and end in the local time of the club, like Elkato stores it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Booking {
+    pub id: u32,
+    /// The ID of the resource.
+    pub resource: String,
+    pub owner: String,
+    pub booker: String,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub description: String,
+    /// Cancelled bookings are inactive.
+    pub active: bool,
+}
+
+/// The state of the club served by the mock server.
+#[derive(Clone, Debug)]
+pub struct Data {
+    pub club: String,
+    pub timezone: Tz,
+    pub users: Vec<User>,
+    pub resources: Vec<Resource>,
+    pub bookings: Vec<Booking>,
+    /// The number of results `search.php` renders per page.
+    pub page_size: usize,
+    /// Redirect to the entry after saving it, instead of rendering a page linking to it, and to
+    /// the login page on wrong credentials, instead of rendering the login form.
+    pub redirect: bool,
+}
+
+impl Default for Data {
+    fn default() -> Self {
+        Self {
+            club: "demo".into(),
+            timezone: chrono_tz::Europe::Berlin,
+            users: vec![],
+            resources: vec![],
+            bookings: vec![],
+            page_size: 10,
+            redirect: false,
+        }
+    }
+}
+
+impl Data {
+    /// A club with a few users and resources, and a week of bookings around `today`.
+    pub fn seeded(today: NaiveDate) -> Self {
+        let user = |username: &str, password: &str| User {
+            username: username.into(),
+            password: password.into(),
+        };
+        let resource = |id: &str, name: &str, group: Option<&str>| Resource {
+            id: id.into(),
+            name: name.into(),
+            group: group.map(Into::into),
+        };
+
+        let mut data = Self {
+            users: vec![
+                user("demo", "demo"),
+                user("admin", "admin"),
+                user("jürgen", "geheim"),
+            ],
+            resources: vec![
+                resource("3", "VW Golf", Some("PKW")),
+                resource("7", "Opel Corsa", Some("PKW")),
+                resource("12", "Transporter", Some("Transporter")),
+                resource("15", "Lastenrad", None),
+            ],
+            ..Default::default()
+        };
+
+        let at = |days: i64, hour: u32, minute: u32| {
+            (today + Duration::days(days))
+                .and_time(NaiveTime::from_hms_opt(hour, minute, 0).unwrap())
+        };
+
+        data.add("3", "demo", at(-3, 8, 0), at(-3, 10, 30), "Einkauf");
+        data.add("12", "demo", at(-1, 9, 0), at(-1, 17, 0), "Umzug");
+        data.add(
+            "3",
+            "jürgen",
+            at(0, 7, 0),
+            at(0, 9, 0),
+            "Fahrt nach München",
+        );
+        data.add("15", "demo", at(0, 12, 0), at(0, 13, 0), "");
+        data.add("7", "admin", at(1, 18, 0), at(3, 18, 0), "Wochenende");
+        data.add("3", "demo", at(2, 14, 0), at(2, 15, 0), "Arzttermin");
+        data.add(
+            "12",
+            "jürgen",
+            at(4, 6, 0),
+            at(4, 20, 0),
+            "Sperrmüll & Wertstoffhof",
+        );
+        data.add(
+            "15",
+            "demo",
+            at(5, 10, 0),
+            at(5, 11, 0),
+            "<Getränke> für die Feier",
+        );
+
+        // one made by the admin on behalf of the demo user, and a cancelled one
+        let id = data.add("7", "demo", at(6, 8, 0), at(6, 12, 0), "Ausflug");
+        data.booking_mut(id).unwrap().booker = "admin".into();
+        let id = data.add("3", "demo", at(1, 8, 0), at(1, 9, 0), "storniert");
+        data.booking_mut(id).unwrap().active = false;
+
+        data
+    }
+
+    /// Add an active booking, made by its owner. Returns the ID of the new booking.
+    pub fn add(
+        &mut self,
+        resource: &str,
+        owner: &str,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        description: &str,
+    ) -> u32 {
+        let id = self.bookings.iter().map(|b| b.id).max().unwrap_or(1000) + 1;
+        self.bookings.push(Booking {
+            id,
+            resource: resource.into(),
+            owner: owner.into(),
+            booker: owner.into(),
+            start,
+            end,
+            description: description.into(),
+            active: true,
+        });
+        id
+    }
+
+    pub fn booking(&self, id: u32) -> Option<&Booking> {
+        self.bookings.iter().find(|b| b.id == id)
+    }
+
+    pub fn booking_mut(&mut self, id: u32) -> Option<&mut Booking> {
+        self.bookings.iter_mut().find(|b| b.id == id)
+    }
+
+    pub fn resource(&self, id: &str) -> Option<&Resource> {
+        self.resources.iter().find(|r| r.id == id)
+    }
+
+    /// Check if the username and password belong to a user of the club.
+    pub fn authenticate(&self, username: &str, password: &str) -> bool {
+        self.users
+            .iter()
+            .any(|u| u.username == username && u.password == password)
+    }
+}
@@ -0,0 +1,136 @@
+//! A fake Elkato server, for testing and development.
+//!
+//! It serves the pages the API uses from an in-memory [`Data`] set. It also acts as a CORS proxy,
+//! in both the "prepend" and the "query" style, using `elkato-proxy`, only forwarding requests to
+//! itself.
+
+pub mod data;
+
+mod pages;
+mod server;
+
+pub use data::Data;
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use elkato_api::{cors::CorsProxy, Api, Credentials};
+use elkato_proxy::{Config, ProxyServer};
+use std::{
+    io,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+use url::Url;
+
+/// Today, in the timezone of the seeded club.
+pub fn today() -> NaiveDate {
+    Utc::now()
+        .with_timezone(&Data::default().timezone)
+        .date_naive()
+}
+
+/// A point in time, relative to today, in the timezone of the seeded club.
+pub fn at(days: i64, hour: u32) -> DateTime<Utc> {
+    Data::default()
+        .timezone
+        .from_local_datetime(
+            &(today() + Duration::days(days))
+                .and_time(NaiveTime::from_hms_opt(hour, 0, 0).unwrap()),
+        )
+        .earliest()
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+pub(crate) struct State {
+    data: Data,
+    /// Fail all requests with this status.
+    failure: Option<u16>,
+}
+
+/// A running mock server, which gets stopped when dropped.
+pub struct MockServer {
+    server: ProxyServer,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockServer {
+    /// Start a mock server on a random, local port.
+    pub async fn start(data: Data) -> io::Result<Self> {
+        Self::bind(SocketAddr::from(([127, 0, 0, 1], 0)), data).await
+    }
+
+    /// Start a mock server on a random, local port, with the club seeded around today.
+    pub async fn seeded() -> io::Result<Self> {
+        Self::start(Data::seeded(today())).await
+    }
+
+    /// Start a mock server on the provided address.
+    pub async fn bind(addr: SocketAddr, data: Data) -> io::Result<Self> {
+        let state = Arc::new(Mutex::new(State {
+            data,
+            failure: None,
+        }));
+
+        let config = |addr: SocketAddr| Config {
+            allow: vec![format!("http://{addr}").parse().unwrap()],
+            ..Default::default()
+        };
+        let server = ProxyServer::bind_with(addr, config, server::router(state.clone())).await?;
+
+        log::info!("Mock server listening on {}", server.prepend_url());
+
+        Ok(Self { server, state })
+    }
+
+    /// The frontend URL of the fake Elkato instance.
+    pub fn url(&self) -> Url {
+        self.server.prepend_url().join("buchung/").unwrap()
+    }
+
+    /// The URL to use with `CorsProxy::Prepend`.
+    pub fn prepend_proxy_url(&self) -> Url {
+        self.server.prepend_url()
+    }
+
+    /// The URL to use with `CorsProxy::Query`, using the `url` parameter.
+    pub fn query_proxy_url(&self) -> Url {
+        self.server.query_url()
+    }
+
+    /// An API client for the user "demo" of the club.
+    pub fn api(&self, proxy: CorsProxy, password: &str) -> Api {
+        Api::new(
+            self.url(),
+            proxy,
+            Credentials {
+                username: "demo".into(),
+                password: password.into(),
+                club: self.data().club,
+            },
+        )
+        .unwrap()
+    }
+
+    /// A snapshot of the current data.
+    pub fn data(&self) -> Data {
+        self.state.lock().unwrap().data.clone()
+    }
+
+    /// Modify the current data.
+    pub fn with_data<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut Data) -> R,
+    {
+        f(&mut self.state.lock().unwrap().data)
+    }
+
+    /// Let all requests fail with a status code, or go back to normal with `None`.
+    pub fn fail_with(&self, status: Option<u16>) {
+        self.state.lock().unwrap().failure = status;
+    }
+
+    /// Wait for the server to stop.
+    pub async fn run(self) {
+        self.server.run().await
+    }
+}
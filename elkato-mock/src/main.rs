@@ -0,0 +1,33 @@
+use elkato_mock::{Data, MockServer};
+use std::net::SocketAddr;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env_logger::init();
+
+    let addr: SocketAddr = std::env::args()
+        .nth(1)
+        .as_deref()
+        .unwrap_or("127.0.0.1:8081")
+        .parse()?;
+
+    let data = Data::seeded(elkato_mock::today());
+    let users = data
+        .users
+        .iter()
+        .map(|u| format!("{}/{}", u.username, u.password))
+        .collect::<Vec<_>>();
+    let club = data.club.clone();
+
+    let server = MockServer::bind(addr, data).await?;
+
+    println!("Elkato:      {}", server.url());
+    println!("Club:        {club}");
+    println!("Users:       {}", users.join(", "));
+    println!("Proxy:       {} (prepend)", server.prepend_proxy_url());
+    println!("             {}?url= (query)", server.query_proxy_url());
+
+    server.run().await;
+
+    Ok(())
+}
@@ -0,0 +1,346 @@
+//! Rendering pages the way Elkato does.
+
+use crate::data::{Booking, Data};
+use chrono::{Datelike, NaiveDateTime, Timelike, Weekday};
+use std::fmt::Write;
+
+const DATE_TIME_FORMAT: &str = "%d.%m.%y, %H:%M";
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE HTML PUBLIC "-//W3C//DTD HTML 4.01 Transitional//EN">
+<html>
+<head>
+<meta http-equiv="Content-Type" content="text/html; charset=utf-8">
+<title>Elkato - {title}</title>
+</head>
+<body>
+<div id="pageBody">
+{body}
+</div>
+</body>
+</html>
+"#,
+        title = escape(title)
+    )
+}
+
+fn weekday(datetime: &NaiveDateTime) -> &'static str {
+    match datetime.weekday() {
+        Weekday::Mon => "Mo",
+        Weekday::Tue => "Di",
+        Weekday::Wed => "Mi",
+        Weekday::Thu => "Do",
+        Weekday::Fri => "Fr",
+        Weekday::Sat => "Sa",
+        Weekday::Sun => "So",
+    }
+}
+
+fn duration(booking: &Booking) -> String {
+    let minutes = (booking.end - booking.start).num_minutes();
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+fn entry_link(club: &str, id: u32) -> String {
+    format!(
+        "view_entry.php?club={}&amp;id={id}",
+        url::form_urlencoded::byte_serialize(club.as_bytes()).collect::<String>()
+    )
+}
+
+fn resource_name(data: &Data, id: &str) -> String {
+    data.resource(id)
+        .map(|r| r.name.clone())
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// An error message, rendered in red.
+pub fn error(message: &str) -> String {
+    page(
+        "Fehler",
+        &format!(
+            r#"<p><font color="red">Fehler: {}</font></p>"#,
+            escape(message)
+        ),
+    )
+}
+
+pub fn login(club: &str) -> String {
+    page(
+        "Anmeldung",
+        &format!(
+            r#"<h3>Anmeldung</h3>
+<form action="login.php" method="post">
+<input type="hidden" name="club" value="{}">
+<table>
+<tr><td>Benutzer:</td><td><input type="text" name="username"></td></tr>
+<tr><td>Passwort:</td><td><input type="password" name="password"></td></tr>
+</table>
+<input type="submit" value="Anmelden">
+</form>"#,
+            escape(club)
+        ),
+    )
+}
+
+/// Asks to log in, on the start page.
+pub fn login_required() -> String {
+    page(
+        "Anmeldung",
+        r#"<p>Bitte melden Sie sich an.</p>
+<p><a href="index.php">Zur Anmeldung</a></p>"#,
+    )
+}
+
+/// The search form, with the resource and user selectors.
+pub fn search_form(data: &Data) -> String {
+    let mut rooms = String::new();
+    let mut groups: Vec<Option<&str>> = vec![];
+    for resource in &data.resources {
+        if !groups.contains(&resource.group.as_deref()) {
+            groups.push(resource.group.as_deref());
+        }
+    }
+    for group in groups {
+        let options = data
+            .resources
+            .iter()
+            .filter(|r| r.group.as_deref() == group)
+            .map(|r| {
+                format!(
+                    r#"<option value="{}">{}</option>"#,
+                    escape(&r.id),
+                    escape(&r.name)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        match group {
+            Some(group) => {
+                let _ = writeln!(
+                    rooms,
+                    "<optgroup label=\"{}\">\n{options}\n</optgroup>",
+                    escape(group)
+                );
+            }
+            None => {
+                let _ = writeln!(rooms, "{options}");
+            }
+        }
+    }
+
+    let users = data
+        .users
+        .iter()
+        .map(|u| format!(r#"<option value="{0}">{0}</option>"#, escape(&u.username)))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    page(
+        "Suche",
+        &format!(
+            r#"<h3>Suche</h3>
+<form action="search.php" method="get">
+<input type="hidden" name="club" value="{club}">
+<table>
+<tr><td>Ressource:</td><td>
+<select name="sel_room">
+<option value="all" selected>Alle</option>
+{rooms}</select>
+</td></tr>
+<tr><td>Besitzer:</td><td>
+<select name="sel_owner">
+<option value="all" selected>Alle</option>
+{users}
+</select>
+</td></tr>
+</table>
+<input type="submit" value="Suchen">
+</form>"#,
+            club = escape(&data.club)
+        ),
+    )
+}
+
+/// A page of search results, `offset` is the zero-based index of the first result.
+pub fn search_result(data: &Data, bookings: &[&Booking], offset: usize) -> String {
+    if bookings.is_empty() {
+        return page(
+            "Suche",
+            "<h3>Suchergebnis</h3>\n<B>Die Suche ergab keine Treffer!</B>",
+        );
+    }
+
+    let total = bookings.len();
+    let to = (offset + data.page_size).min(total);
+    let paging = format!("<p><b>Treffer {} - {to} von {total}</b></p>", offset + 1);
+
+    let mut rows = String::new();
+    for booking in bookings.iter().skip(offset).take(data.page_size) {
+        // inactive bookings are grayed out
+        let cell = |value: String| match booking.active {
+            true => format!("<td>{value}</td>"),
+            false => format!(r##"<td><font color="#999999">{value}</font></td>"##),
+        };
+        let _ = writeln!(
+            rows,
+            r#"<tr><td><a href="{link}">{id}</a></td>{resource}{owner}{start_day}{start}{end_day}{end}{duration}{booker}{description}</tr>"#,
+            link = entry_link(&data.club, booking.id),
+            id = booking.id,
+            resource = cell(escape(&resource_name(data, &booking.resource))),
+            owner = cell(escape(&booking.owner)),
+            start_day = cell(weekday(&booking.start).to_string()),
+            start = cell(booking.start.format(DATE_TIME_FORMAT).to_string()),
+            end_day = cell(weekday(&booking.end).to_string()),
+            end = cell(booking.end.format(DATE_TIME_FORMAT).to_string()),
+            duration = cell(duration(booking)),
+            booker = cell(escape(&booking.booker)),
+            description = cell(escape(&booking.description)),
+        );
+    }
+
+    let next = if to < total {
+        format!(
+            r#"<a href="search.php?club={}&amp;search_pos={to}">Weiter</a>"#,
+            escape(&data.club)
+        )
+    } else {
+        String::new()
+    };
+
+    page(
+        "Suche",
+        &format!(
+            r##"<h3>Suchergebnis</h3>
+{paging}
+<table border="1" bordercolor="#000000" cellspacing="0" cellpadding="2">
+<tr><th>Nr.</th><th>Ressource</th><th>Benutzer</th><th>Tag</th><th>Beginn</th><th>Tag</th><th>Ende</th><th>Dauer</th><th>Gebucht von</th><th>Bemerkung</th></tr>
+{rows}</table>
+{paging}
+{next}"##
+        ),
+    )
+}
+
+/// The detail page of a booking.
+pub fn details(data: &Data, booking: &Booking) -> String {
+    let row = |label: &str, value: &str| {
+        format!(
+            "<tr><td><b>{label}:</b></td><td>{}</td></tr>\n",
+            escape(value)
+        )
+    };
+
+    let mut rows = String::new();
+    rows += &row("Nr.", &booking.id.to_string());
+    rows += &row("Ressource", &resource_name(data, &booking.resource));
+    rows += &row("Benutzer", &booking.owner);
+    rows += &row("Gebucht von", &booking.booker);
+    rows += &row(
+        "Beginn",
+        &booking.start.format(DATE_TIME_FORMAT).to_string(),
+    );
+    rows += &row("Ende", &booking.end.format(DATE_TIME_FORMAT).to_string());
+    rows += &row("Dauer", &duration(booking));
+    rows += &row("Bemerkung", &booking.description);
+    rows += &row("Erstellt von", &booking.booker);
+
+    page(
+        "Buchung",
+        &format!(
+            r#"<h3>Buchung {id}</h3>
+<table border="0">
+{rows}</table>
+<p><a href="edit_entry.php?club={club}&amp;id={id}">Bearbeiten</a></p>"#,
+            id = booking.id,
+            club = escape(&data.club),
+        ),
+    )
+}
+
+/// The booking entry form, pre-filled with an existing booking.
+pub fn entry_form(data: &Data, booking: &Booking) -> String {
+    let datetime = |prefix: &str, datetime: &NaiveDateTime| {
+        [
+            ("day", datetime.day()),
+            ("month", datetime.month()),
+            ("year", datetime.year() as u32),
+            ("hour", datetime.hour()),
+            ("minute", datetime.minute()),
+        ]
+        .iter()
+        .map(|(name, value)| {
+            format!(r#"<input type="text" name="{prefix}_{name}" value="{value}">"#)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+    };
+
+    let rooms = data
+        .resources
+        .iter()
+        .map(|r| {
+            format!(
+                r#"<option value="{}"{}>{}</option>"#,
+                escape(&r.id),
+                if r.id == booking.resource {
+                    " selected"
+                } else {
+                    ""
+                },
+                escape(&r.name)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    page(
+        "Buchung bearbeiten",
+        &format!(
+            r#"<h3>Buchung bearbeiten</h3>
+<form action="edit_entry_handler.php" method="post">
+<input type="hidden" name="club" value="{club}">
+<input type="hidden" name="id" value="{id}">
+<input type="hidden" name="create_by" value="{owner}">
+<select name="room">
+{rooms}
+</select>
+{start}
+{end}
+<textarea name="description">{description}</textarea>
+<input type="submit" value="Speichern">
+</form>"#,
+            club = escape(&data.club),
+            id = booking.id,
+            owner = escape(&booking.owner),
+            start = datetime("start", &booking.start),
+            end = datetime("end", &booking.end),
+            description = escape(&booking.description),
+        ),
+    )
+}
+
+/// The response to successfully submitting the booking entry form.
+pub fn entry_saved(data: &Data, id: u32) -> String {
+    page(
+        "Buchung",
+        &format!(
+            r#"<p>Die Buchung wurde gespeichert: <a href="{}">{id}</a></p>"#,
+            entry_link(&data.club, id)
+        ),
+    )
+}
+
+/// The response to successfully cancelling or deleting a booking.
+pub fn message(message: &str) -> String {
+    page("Buchung", &format!("<p>{}</p>", escape(message)))
+}
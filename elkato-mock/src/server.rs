@@ -0,0 +1,333 @@
+use crate::{data::Data, pages, State};
+use axum::{
+    extract::{Form, Query},
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Router,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tower_http::cors::CorsLayer;
+
+type Shared = Arc<Mutex<State>>;
+type Params = HashMap<String, String>;
+
+pub(crate) fn router(state: Shared) -> Router {
+    let buchung = Router::new()
+        .route("/login.php", get(login))
+        .route("/search.php", get(search))
+        .route("/view_entry.php", get(view_entry))
+        .route("/edit_entry.php", get(edit_entry))
+        .route("/edit_entry_handler.php", post(edit_entry_handler))
+        .route("/cancel_entry.php", post(cancel_entry))
+        .route("/del_entry.php", post(del_entry))
+        .with_state(state);
+
+    Router::new()
+        .nest("/buchung", buchung)
+        .layer(CorsLayer::very_permissive())
+}
+
+/// Check the state, the club and the credentials of a request. Returns the authenticated user.
+fn authorize(state: &State, headers: &HeaderMap, params: &Params) -> Result<String, Box<Response>> {
+    if let Some(status) = state.failure {
+        return Err(Box::new(
+            StatusCode::from_u16(status)
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+                .into_response(),
+        ));
+    }
+
+    let data = &state.data;
+    let club = params.get("club").map(String::as_str).unwrap_or_default();
+    if club != data.club {
+        return Err(Box::new(error(&format!("Unbekannter Verein \"{club}\""))));
+    }
+
+    let Some(credentials) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Basic "))
+        .and_then(|value| STANDARD.decode(value).ok())
+        .and_then(|value| String::from_utf8(value).ok())
+    else {
+        return Err(Box::new(
+            (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, r#"Basic realm="Elkato""#)],
+            )
+                .into_response(),
+        ));
+    };
+
+    match credentials.split_once(':') {
+        Some((username, password)) if data.authenticate(username, password) => {
+            Ok(username.to_string())
+        }
+        _ if data.redirect => Err(Box::new(
+            Redirect::to(&format!("login.php?club={}", encode(club))).into_response(),
+        )),
+        _ => Err(Box::new(Html(pages::login(club)).into_response())),
+    }
+}
+
+/// Run a handler on the data, after authorizing the request.
+fn handle<F>(state: &Shared, headers: &HeaderMap, params: &Params, f: F) -> Response
+where
+    F: FnOnce(&mut Data, &str) -> Response,
+{
+    let mut state = state.lock().unwrap();
+    match authorize(&state, headers, params) {
+        Ok(user) => f(&mut state.data, &user),
+        Err(response) => *response,
+    }
+}
+
+fn encode(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+fn error(message: &str) -> Response {
+    Html(pages::error(message)).into_response()
+}
+
+fn booking_id(params: &Params) -> Option<u32> {
+    params.get("id")?.trim().parse().ok()
+}
+
+/// Parse a date filter of the search, like `s_from`.
+fn date_filter(params: &Params, prefix: &str) -> Option<NaiveDate> {
+    if params.get(prefix).map(String::as_str) != Some("1") {
+        return None;
+    }
+    let number = |name: &str| params.get(&format!("{prefix}_{name}"))?.parse().ok();
+    NaiveDate::from_ymd_opt(
+        number("year")?,
+        number("month")? as u32,
+        number("day")? as u32,
+    )
+}
+
+/// Parse a point in time of the booking entry form, like `start`.
+fn form_datetime(params: &Params, prefix: &str) -> Option<NaiveDateTime> {
+    let number =
+        |name: &str| -> Option<u32> { params.get(&format!("{prefix}_{name}"))?.parse().ok() };
+    Some(
+        NaiveDate::from_ymd_opt(number("year")? as i32, number("month")?, number("day")?)?
+            .and_time(NaiveTime::from_hms_opt(
+                number("hour")?,
+                number("minute")?,
+                0,
+            )?),
+    )
+}
+
+fn may_modify(user: &str, owner: &str) -> bool {
+    user == owner || user == "admin"
+}
+
+/// Where wrong credentials get redirected to, in the redirect mode.
+///
+/// It only asks to log in, without the login form, so that only the URL tells that the
+/// credentials were rejected.
+async fn login() -> Response {
+    Html(pages::login_required()).into_response()
+}
+
+async fn search(
+    state: axum::extract::State<Shared>,
+    headers: HeaderMap,
+    Query(params): Query<Params>,
+) -> Response {
+    handle(&state, &headers, &params, |data, _| {
+        let Some(offset) = params.get("search_pos") else {
+            return Html(pages::search_form(data)).into_response();
+        };
+        let offset = offset.parse().unwrap_or(0);
+
+        let matches = |name: &str, value: &str| match params.get(name).map(String::as_str) {
+            None | Some("all") => true,
+            Some(expected) => expected == value,
+        };
+
+        let active = params.contains_key("active");
+        let inactive = params.contains_key("inactive");
+        let (active, inactive) = if active || inactive {
+            (active, inactive)
+        } else {
+            (true, false)
+        };
+
+        let s_from = date_filter(&params, "s_from");
+        let s_to = date_filter(&params, "s_to");
+        let e_from = date_filter(&params, "e_from");
+        let e_to = date_filter(&params, "e_to");
+
+        let mut bookings: Vec<_> = data
+            .bookings
+            .iter()
+            .filter(|b| if b.active { active } else { inactive })
+            .filter(|b| matches("sel_room", &b.resource))
+            .filter(|b| matches("sel_owner", &b.owner))
+            .filter(|b| matches("sel_booker", &b.booker))
+            .filter(|b| s_from.is_none_or(|d| b.start.date() >= d))
+            .filter(|b| s_to.is_none_or(|d| b.start.date() <= d))
+            .filter(|b| e_from.is_none_or(|d| b.end.date() >= d))
+            .filter(|b| e_to.is_none_or(|d| b.end.date() <= d))
+            .collect();
+        bookings.sort_by_key(|b| (b.start, b.id));
+
+        Html(pages::search_result(data, &bookings, offset)).into_response()
+    })
+}
+
+async fn view_entry(
+    state: axum::extract::State<Shared>,
+    headers: HeaderMap,
+    Query(params): Query<Params>,
+) -> Response {
+    handle(&state, &headers, &params, |data, _| {
+        match booking_id(&params).and_then(|id| data.booking(id)) {
+            Some(booking) => Html(pages::details(data, booking)).into_response(),
+            None => StatusCode::NOT_FOUND.into_response(),
+        }
+    })
+}
+
+async fn edit_entry(
+    state: axum::extract::State<Shared>,
+    headers: HeaderMap,
+    Query(params): Query<Params>,
+) -> Response {
+    handle(&state, &headers, &params, |data, _| {
+        match booking_id(&params).and_then(|id| data.booking(id)) {
+            Some(booking) => Html(pages::entry_form(data, booking)).into_response(),
+            None => StatusCode::NOT_FOUND.into_response(),
+        }
+    })
+}
+
+async fn edit_entry_handler(
+    state: axum::extract::State<Shared>,
+    headers: HeaderMap,
+    Form(params): Form<Params>,
+) -> Response {
+    handle(&state, &headers, &params, |data, user| {
+        let (Some(start), Some(end)) = (
+            form_datetime(&params, "start"),
+            form_datetime(&params, "end"),
+        ) else {
+            return error("Ungültiges Datum");
+        };
+        if start >= end {
+            return error("Das Ende muss nach dem Beginn liegen");
+        }
+
+        let resource = params.get("room").cloned().unwrap_or_default();
+        if data.resource(&resource).is_none() {
+            return error("Unbekannte Ressource");
+        }
+        let owner = params
+            .get("create_by")
+            .cloned()
+            .unwrap_or_else(|| user.to_string());
+        let description = params.get("description").cloned().unwrap_or_default();
+
+        let id = match params.get("id") {
+            Some(_) => match booking_id(&params).and_then(|id| data.booking(id)) {
+                Some(booking) if !may_modify(user, &booking.owner) => {
+                    return error("Sie sind nicht der Besitzer dieser Buchung");
+                }
+                Some(booking) => Some(booking.id),
+                None => return StatusCode::NOT_FOUND.into_response(),
+            },
+            None => None,
+        };
+
+        if let Some(other) = data.bookings.iter().find(|b| {
+            b.active && Some(b.id) != id && b.resource == resource && b.start < end && b.end > start
+        }) {
+            return error(&format!(
+                "Die Buchung überschneidet sich mit Buchung {}",
+                other.id
+            ));
+        }
+
+        let id = match id {
+            Some(id) => {
+                let booking = data.booking_mut(id).unwrap();
+                booking.resource = resource;
+                booking.start = start;
+                booking.end = end;
+                booking.description = description;
+                id
+            }
+            None => {
+                let id = data.add(&resource, &owner, start, end, &description);
+                data.booking_mut(id).unwrap().booker = user.to_string();
+                id
+            }
+        };
+
+        match data.redirect {
+            true => Redirect::to(&format!(
+                "view_entry.php?club={}&id={id}",
+                encode(&data.club)
+            ))
+            .into_response(),
+            false => Html(pages::entry_saved(data, id)).into_response(),
+        }
+    })
+}
+
+/// Cancel or delete a booking, after checking it may be modified.
+fn modify<F>(data: &mut Data, user: &str, params: &Params, f: F) -> Response
+where
+    F: FnOnce(&mut Data, u32) -> &'static str,
+{
+    let Some(booking) = booking_id(params).and_then(|id| data.booking(id)) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    if !may_modify(user, &booking.owner) {
+        return error("Sie sind nicht der Besitzer dieser Buchung");
+    }
+    let now = Utc::now().with_timezone(&data.timezone).naive_local();
+    if booking.start <= now {
+        return error("Die Buchung hat bereits begonnen");
+    }
+
+    let id = booking.id;
+    Html(pages::message(f(data, id))).into_response()
+}
+
+async fn cancel_entry(
+    state: axum::extract::State<Shared>,
+    headers: HeaderMap,
+    Form(params): Form<Params>,
+) -> Response {
+    handle(&state, &headers, &params, |data, user| {
+        modify(data, user, &params, |data, id| {
+            data.booking_mut(id).unwrap().active = false;
+            "Die Buchung wurde storniert."
+        })
+    })
+}
+
+async fn del_entry(
+    state: axum::extract::State<Shared>,
+    headers: HeaderMap,
+    Form(params): Form<Params>,
+) -> Response {
+    handle(&state, &headers, &params, |data, user| {
+        modify(data, user, &params, |data, id| {
+            data.bookings.retain(|b| b.id != id);
+            "Die Buchung wurde gelöscht."
+        })
+    })
+}
@@ -0,0 +1,183 @@
+//! A CORS proxy for Elkato, replacing the PHP based `proxy.php`.
+//!
+//! It understands both styles of `CorsProxy` in `elkato-api`: the target URL can be appended
+//! to the path of the proxy ("prepend"), or provided in the `url` query parameter ("query").
+//! Requests are only forwarded to the allowed targets, which default to the Elkato instances.
+//! Redirects get followed, and the final URL is reported in the `Content-Location` header.
+
+mod server;
+
+use axum::{http::HeaderName, Router};
+use std::{
+    fmt, io,
+    net::{SocketAddr, TcpListener},
+    str::FromStr,
+};
+use tokio::task::JoinHandle;
+use url::Url;
+
+/// A scheme and host, and optionally a port, the proxy may forward requests to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Target {
+    pub scheme: String,
+    pub host: String,
+    /// The port, defaults to the default port of the scheme.
+    pub port: Option<u16>,
+}
+
+impl Target {
+    pub fn new(scheme: impl Into<String>, host: impl Into<String>) -> Self {
+        Self {
+            scheme: scheme.into(),
+            host: host.into(),
+            port: None,
+        }
+    }
+
+    /// Check if a URL points to this target.
+    pub fn matches(&self, url: &Url) -> bool {
+        let port = self.port.or(match self.scheme.as_str() {
+            "http" => Some(80),
+            "https" => Some(443),
+            _ => None,
+        });
+
+        url.scheme() == self.scheme
+            && url.host_str() == Some(self.host.as_str())
+            && url.port_or_known_default() == port
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}://{}", self.scheme, self.host)?;
+        if let Some(port) = self.port {
+            write!(f, ":{port}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a target from a URL like `https://www.elkato.de`.
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let url = Url::parse(s).map_err(|err| format!("Invalid target '{s}': {err}"))?;
+        let host = url
+            .host_str()
+            .ok_or_else(|| format!("Target without a host: {s}"))?;
+        if url.path() != "/" || url.query().is_some() {
+            return Err(format!("Target must not have a path or query: {s}"));
+        }
+
+        Ok(Self {
+            scheme: url.scheme().to_string(),
+            host: host.to_string(),
+            port: url.port(),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The targets requests may be forwarded to.
+    pub allow: Vec<Target>,
+    /// The origins which may use the proxy, any origin if empty.
+    pub origins: Vec<String>,
+    /// The number of redirects to follow, on behalf of the client.
+    pub max_redirects: usize,
+    /// The header to restore the `Authorization` header from, for clients which can't send it.
+    pub authorization_header: HeaderName,
+}
+
+/// Allow the Elkato instances only, like `proxy.php` does.
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            allow: vec![
+                Target::new("https", "elkato.de"),
+                Target::new("https", "www.elkato.de"),
+            ],
+            origins: vec![],
+            max_redirects: 10,
+            authorization_header: HeaderName::from_static("x-proxy-authorization"),
+        }
+    }
+}
+
+impl Config {
+    /// Check if requests may be forwarded to a URL.
+    pub fn allows(&self, url: &Url) -> bool {
+        self.allow.iter().any(|target| target.matches(url))
+    }
+}
+
+/// A running proxy, which gets stopped when dropped.
+pub struct ProxyServer {
+    addr: SocketAddr,
+    handle: JoinHandle<()>,
+}
+
+impl ProxyServer {
+    /// Start a proxy on a random, local port.
+    pub async fn start(config: Config) -> io::Result<Self> {
+        Self::bind(SocketAddr::from(([127, 0, 0, 1], 0)), config).await
+    }
+
+    /// Start a proxy on the provided address.
+    pub async fn bind(addr: SocketAddr, config: Config) -> io::Result<Self> {
+        Self::bind_with(addr, |_| config, Router::new()).await
+    }
+
+    /// Start a proxy on the provided address, serving some routes itself.
+    ///
+    /// Only requests which none of the routes match get forwarded. The configuration gets created
+    /// once the address is known, which allows forwarding requests to the server itself.
+    pub async fn bind_with<F>(addr: SocketAddr, config: F, routes: Router) -> io::Result<Self>
+    where
+        F: FnOnce(SocketAddr) -> Config,
+    {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+
+        let router = routes.fallback_service(server::router(config(addr)));
+        let server = axum::Server::from_tcp(listener)
+            .map_err(io::Error::other)?
+            .serve(router.into_make_service());
+
+        let handle = tokio::spawn(async move {
+            if let Err(err) = server.await {
+                log::error!("Proxy failed: {err}");
+            }
+        });
+
+        log::info!("Proxy listening on {addr}");
+
+        Ok(Self { addr, handle })
+    }
+
+    /// The URL to use with `CorsProxy::Prepend`.
+    pub fn prepend_url(&self) -> Url {
+        Url::parse(&format!("http://{}/", self.addr)).unwrap()
+    }
+
+    /// The URL to use with `CorsProxy::Query`, using the `url` parameter.
+    pub fn query_url(&self) -> Url {
+        self.prepend_url().join("proxy.php").unwrap()
+    }
+
+    /// Wait for the proxy to stop.
+    pub async fn run(mut self) {
+        if let Err(err) = (&mut self.handle).await {
+            log::error!("Proxy failed: {err}");
+        }
+    }
+}
+
+impl Drop for ProxyServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
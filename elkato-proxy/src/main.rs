@@ -0,0 +1,57 @@
+use elkato_proxy::{Config, ProxyServer, Target};
+use std::net::SocketAddr;
+
+/// Read a comma separated list from an environment variable.
+fn list(name: &str) -> Option<Vec<String>> {
+    let value = std::env::var(name).ok()?;
+    Some(
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(ToString::to_string)
+            .collect(),
+    )
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env_logger::init();
+
+    let addr: SocketAddr = std::env::args()
+        .nth(1)
+        .as_deref()
+        .unwrap_or("127.0.0.1:9999")
+        .parse()?;
+
+    let mut config = Config::default();
+    if let Some(allow) = list("ELKATO_PROXY_ALLOW") {
+        config.allow = allow
+            .iter()
+            .map(|target| target.parse::<Target>())
+            .collect::<Result<_, _>>()
+            .map_err(anyhow::Error::msg)?;
+    }
+    if let Some(origins) = list("ELKATO_PROXY_ORIGINS") {
+        config.origins = origins;
+    }
+    if let Ok(header) = std::env::var("ELKATO_PROXY_AUTHORIZATION_HEADER") {
+        config.authorization_header = header.parse()?;
+    }
+
+    let allow = config
+        .allow
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+
+    let server = ProxyServer::bind(addr, config).await?;
+
+    println!("Proxy:       {} (prepend)", server.prepend_url());
+    println!("             {}?url= (query)", server.query_url());
+    println!("Allowed:     {}", allow.join(", "));
+
+    server.run().await;
+
+    Ok(())
+}
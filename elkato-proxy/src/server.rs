@@ -0,0 +1,239 @@
+use crate::Config;
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Router,
+};
+use std::{sync::Arc, time::Duration};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use url::Url;
+
+/// The request headers passed on to the target.
+const REQUEST_HEADERS: [HeaderName; 4] = [
+    header::AUTHORIZATION,
+    header::CONTENT_TYPE,
+    header::ACCEPT,
+    header::ACCEPT_LANGUAGE,
+];
+
+/// The response headers passed back to the client.
+const RESPONSE_HEADERS: [HeaderName; 3] = [
+    header::CONTENT_TYPE,
+    header::WWW_AUTHENTICATE,
+    header::CONTENT_LANGUAGE,
+];
+
+struct Proxy {
+    config: Config,
+    client: reqwest::Client,
+}
+
+type Shared = Arc<Proxy>;
+
+pub(crate) fn router(config: Config) -> Router {
+    let cors = cors(&config);
+
+    let client = reqwest::Client::builder()
+        // redirects are followed manually, checking the target and keeping the credentials
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(Duration::from_secs(30))
+        .build()
+        .expect("Failed to create HTTP client");
+
+    Router::new()
+        .fallback(proxy)
+        .with_state(Arc::new(Proxy { config, client }))
+        .layer(cors)
+}
+
+/// Answer preflight requests, and allow sending credentials.
+fn cors(config: &Config) -> CorsLayer {
+    let origin = match config.origins.is_empty() {
+        true => AllowOrigin::mirror_request(),
+        false => AllowOrigin::list(
+            config
+                .origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        ),
+    };
+
+    CorsLayer::new()
+        .allow_origin(origin)
+        .allow_credentials(true)
+        .allow_methods([Method::GET, Method::HEAD, Method::POST])
+        .allow_headers(
+            REQUEST_HEADERS
+                .into_iter()
+                .chain([config.authorization_header.clone()])
+                .collect::<Vec<_>>(),
+        )
+        .expose_headers(
+            RESPONSE_HEADERS
+                .into_iter()
+                .chain([header::CONTENT_LOCATION])
+                .collect::<Vec<_>>(),
+        )
+        .max_age(Duration::from_secs(24 * 60 * 60))
+}
+
+async fn proxy(
+    State(proxy): State<Shared>,
+    method: Method,
+    headers: HeaderMap,
+    uri: Uri,
+    body: Bytes,
+) -> Response {
+    let target = match target(&uri) {
+        Ok(target) => target,
+        Err(err) => return (StatusCode::BAD_REQUEST, err).into_response(),
+    };
+
+    if !proxy.config.allows(&target) {
+        log::info!("Rejecting request to: {target}");
+        return (StatusCode::FORBIDDEN, format!("Not allowed: {target}")).into_response();
+    }
+
+    proxy.forward(method, &headers, target, body).await
+}
+
+/// Extract the target URL, either from the path ("prepend") or the `url` query parameter
+/// ("query").
+fn target(uri: &Uri) -> Result<Url, String> {
+    let path = percent_encoding::percent_decode_str(uri.path()).decode_utf8_lossy();
+
+    let start = ["http://", "https://"]
+        .iter()
+        .filter_map(|scheme| path.find(scheme))
+        .min();
+
+    let target = match (start, uri.query()) {
+        (Some(start), query) => {
+            let mut target = path[start..].to_string();
+            if let Some(query) = query {
+                target.push(if target.contains('?') { '&' } else { '?' });
+                target.push_str(query);
+            }
+            target
+        }
+        (None, Some(query)) => url::form_urlencoded::parse(query.as_bytes())
+            .find(|(name, _)| name == "url")
+            .map(|(_, value)| value.into_owned())
+            .ok_or("Missing 'url' query parameter")?,
+        (None, None) => return Err("Missing target URL".into()),
+    };
+
+    Url::parse(&target).map_err(|err| format!("Invalid target URL: {err}"))
+}
+
+impl Proxy {
+    async fn forward(
+        &self,
+        mut method: Method,
+        headers: &HeaderMap,
+        mut target: Url,
+        body: Bytes,
+    ) -> Response {
+        let mut body = Some(body).filter(|body| !body.is_empty());
+
+        for _ in 0..=self.config.max_redirects {
+            log::debug!("Proxy: {method} {target}");
+
+            let mut request = self.client.request(method.clone(), target.clone());
+            for name in &REQUEST_HEADERS {
+                if name == header::CONTENT_TYPE && body.is_none() {
+                    continue;
+                }
+                if let Some(value) = headers.get(name) {
+                    request = request.header(name, value);
+                }
+            }
+            if !headers.contains_key(header::AUTHORIZATION) {
+                if let Some(value) = headers.get(&self.config.authorization_header) {
+                    request = request.header(header::AUTHORIZATION, value);
+                }
+            }
+            if let Some(body) = &body {
+                request = request.body(body.clone());
+            }
+
+            let response = match request.send().await {
+                Ok(response) => response,
+                Err(err) => return failure(err),
+            };
+
+            let status = response.status();
+            let location = response
+                .headers()
+                .get(header::LOCATION)
+                .and_then(|location| location.to_str().ok())
+                .and_then(|location| target.join(location).ok());
+
+            match location {
+                Some(location) if status.is_redirection() => {
+                    if !self.config.allows(&location) {
+                        log::info!("Rejecting redirect to: {location}");
+                        return (
+                            StatusCode::FORBIDDEN,
+                            format!("Redirect not allowed: {location}"),
+                        )
+                            .into_response();
+                    }
+
+                    if status == StatusCode::SEE_OTHER
+                        || (method == Method::POST
+                            && matches!(status, StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND))
+                    {
+                        method = Method::GET;
+                        body = None;
+                    }
+                    target = location;
+                }
+                _ => return respond(response).await,
+            }
+        }
+
+        (StatusCode::BAD_GATEWAY, "Too many redirects").into_response()
+    }
+}
+
+/// Pass the response back, telling the client the URL it came from, after following redirects.
+async fn respond(response: reqwest::Response) -> Response {
+    let status = response.status();
+    let headers: Vec<_> = RESPONSE_HEADERS
+        .into_iter()
+        .filter_map(|name| {
+            let value = response.headers().get(&name)?.clone();
+            Some((name, value))
+        })
+        .chain(
+            HeaderValue::from_str(response.url().as_str())
+                .ok()
+                .map(|url| (header::CONTENT_LOCATION, url)),
+        )
+        .collect();
+
+    match response.bytes().await {
+        Ok(body) => {
+            let mut response = (status, body).into_response();
+            // replace the content type of the body, instead of adding another one
+            for (name, value) in headers {
+                response.headers_mut().insert(name, value);
+            }
+            response
+        }
+        Err(err) => failure(err),
+    }
+}
+
+fn failure(err: reqwest::Error) -> Response {
+    log::info!("Failed to contact target: {err}");
+
+    let status = match err.is_timeout() {
+        true => StatusCode::GATEWAY_TIMEOUT,
+        false => StatusCode::BAD_GATEWAY,
+    };
+    (status, err.to_string()).into_response()
+}
@@ -0,0 +1,278 @@
+//! Using the proxy with the API, against the mock server.
+
+use axum::{
+    http::{header, HeaderMap, HeaderName, StatusCode},
+    response::{IntoResponse, Redirect},
+    routing::{get, post},
+    Router,
+};
+use elkato_api::{cors::CorsProxy, Error};
+use elkato_mock::MockServer;
+use elkato_proxy::{Config, ProxyServer, Target};
+use futures::{StreamExt, TryStreamExt};
+use std::net::{SocketAddr, TcpListener};
+use url::Url;
+
+/// Allow requests to the server only.
+fn config_for(url: &Url) -> Config {
+    let target = Target {
+        scheme: url.scheme().into(),
+        host: url.host_str().unwrap().into(),
+        port: url.port(),
+    };
+
+    Config {
+        allow: vec![target],
+        ..Default::default()
+    }
+}
+
+/// A proxy allowing requests to the server only.
+async fn proxy_for(url: &Url) -> ProxyServer {
+    ProxyServer::start(config_for(url)).await.unwrap()
+}
+
+fn styles(proxy: &ProxyServer) -> Vec<CorsProxy> {
+    vec![
+        CorsProxy::Prepend(proxy.prepend_url()),
+        CorsProxy::Query {
+            url: proxy.query_url(),
+            parameter: "url".into(),
+        },
+    ]
+}
+
+#[tokio::test]
+async fn list() {
+    let server = MockServer::seeded().await.unwrap();
+    let proxy = proxy_for(&server.url()).await;
+    let expected = server.data().bookings.iter().filter(|b| b.active).count();
+
+    for style in styles(&proxy) {
+        let bookings: Vec<_> = server
+            .api(style.clone(), "demo")
+            .list_bookings(Default::default())
+            .boxed_local()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(bookings.len(), expected, "Proxy: {style:?}");
+    }
+}
+
+#[tokio::test]
+async fn credentials() {
+    let server = MockServer::seeded().await.unwrap();
+    let proxy = proxy_for(&server.url()).await;
+
+    for style in styles(&proxy) {
+        assert!(server
+            .api(style.clone(), "demo")
+            .verify_credentials()
+            .await
+            .is_ok());
+        assert!(
+            matches!(
+                server
+                    .api(style.clone(), "wrong")
+                    .verify_credentials()
+                    .await,
+                Err(Error::Unauthorized)
+            ),
+            "Proxy: {style:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn authorization_header() {
+    let server = MockServer::seeded().await.unwrap();
+    let proxy = proxy_for(&server.url()).await;
+
+    for style in styles(&proxy) {
+        let style = style.with_authorization_header();
+        assert!(server
+            .api(style.clone(), "demo")
+            .verify_credentials()
+            .await
+            .is_ok());
+        assert!(
+            matches!(
+                server
+                    .api(style.clone(), "wrong")
+                    .verify_credentials()
+                    .await,
+                Err(Error::Unauthorized)
+            ),
+            "Proxy: {style:?}"
+        );
+    }
+
+    // a proxy which doesn't know the header drops it, and Elkato asks for credentials
+    let other = ProxyServer::start(Config {
+        authorization_header: HeaderName::from_static("x-other-authorization"),
+        ..config_for(&server.url())
+    })
+    .await
+    .unwrap();
+    let style = CorsProxy::Prepend(other.prepend_url()).with_authorization_header();
+    assert!(matches!(
+        server.api(style, "demo").verify_credentials().await,
+        Err(Error::Unauthorized)
+    ));
+}
+
+#[tokio::test]
+async fn not_allowed() {
+    let server = MockServer::seeded().await.unwrap();
+
+    // the default only allows Elkato
+    let proxy = ProxyServer::start(Default::default()).await.unwrap();
+    let target = server.url().join("search.php?club=demo").unwrap();
+
+    let client = reqwest::Client::new();
+    for url in [
+        format!("{}{target}", proxy.prepend_url()),
+        format!("{}?url={target}", proxy.query_url()),
+        format!("{}http://www.elkato.de/buchung/", proxy.prepend_url()),
+        format!("{}https://elkato.de.example.com/", proxy.prepend_url()),
+        format!("{}https://www.elkato.de:8443/", proxy.prepend_url()),
+        format!("{}?url=https://example.com/", proxy.query_url()),
+    ] {
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN, "URL: {url}");
+    }
+
+    let response = client.get(proxy.query_url()).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // the proxy of the mock server only forwards to the mock server itself
+    for url in [
+        format!(
+            "{}https://www.elkato.de/buchung/",
+            server.prepend_proxy_url()
+        ),
+        format!("{}?url=https://example.com/", server.query_proxy_url()),
+    ] {
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN, "URL: {url}");
+    }
+}
+
+#[tokio::test]
+async fn preflight() {
+    let proxy = ProxyServer::start(Default::default()).await.unwrap();
+
+    let response = reqwest::Client::new()
+        .request(
+            reqwest::Method::OPTIONS,
+            format!(
+                "{}https://www.elkato.de/buchung/search.php",
+                proxy.prepend_url()
+            ),
+        )
+        .header(header::ORIGIN, "https://frontend.example.com")
+        .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+        .header(
+            header::ACCESS_CONTROL_REQUEST_HEADERS,
+            "authorization,content-type,x-proxy-authorization",
+        )
+        .send()
+        .await
+        .unwrap();
+
+    assert!(response.status().is_success());
+    let headers = response.headers();
+    let get = |name| headers.get(name).unwrap().to_str().unwrap().to_lowercase();
+    assert_eq!(
+        get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+        "https://frontend.example.com"
+    );
+    assert_eq!(get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS), "true");
+    let list = |name| {
+        get(name)
+            .split(',')
+            .map(|value| value.trim().to_string())
+            .collect::<Vec<_>>()
+    };
+    let allowed = list(header::ACCESS_CONTROL_ALLOW_HEADERS);
+    assert!(allowed.iter().any(|h| h == "authorization"), "{allowed:?}");
+    assert!(
+        allowed.iter().any(|h| h == "x-proxy-authorization"),
+        "{allowed:?}"
+    );
+    let methods = list(header::ACCESS_CONTROL_ALLOW_METHODS);
+    assert!(methods.iter().any(|m| m == "post"), "{methods:?}");
+}
+
+/// An upstream server, redirecting around.
+async fn upstream() -> (Url, tokio::task::JoinHandle<()>) {
+    async fn echo(headers: HeaderMap) -> impl IntoResponse {
+        headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    let router = Router::new()
+        .route("/redirect", get(|| async { Redirect::temporary("/echo") }))
+        .route("/submit", post(|| async { Redirect::to("/echo") }))
+        .route("/echo", get(echo))
+        .route(
+            "/away",
+            get(|| async { Redirect::temporary("https://example.com/") }),
+        );
+
+    let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).unwrap();
+    let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+    let server = axum::Server::from_tcp(listener)
+        .unwrap()
+        .serve(router.into_make_service());
+    let handle = tokio::spawn(async move {
+        server.await.unwrap();
+    });
+
+    (url, handle)
+}
+
+#[tokio::test]
+async fn redirects() {
+    let (upstream, handle) = upstream().await;
+    let proxy = proxy_for(&upstream).await;
+    let client = reqwest::Client::new();
+
+    let url = |path: &str| format!("{}{}", proxy.prepend_url(), upstream.join(path).unwrap());
+
+    // credentials are kept when following redirects
+    for request in [
+        client.get(url("redirect")),
+        client.post(url("submit")).body("id=1"),
+    ] {
+        let response = request
+            .basic_auth("demo", Some("demo"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get_all(header::CONTENT_TYPE)
+                .iter()
+                .collect::<Vec<_>>(),
+            ["text/plain; charset=utf-8"]
+        );
+        assert_eq!(
+            response.headers()[header::CONTENT_LOCATION],
+            upstream.join("echo").unwrap().as_str()
+        );
+        assert!(response.text().await.unwrap().starts_with("Basic "));
+    }
+
+    // but never sent elsewhere
+    let response = client.get(url("away")).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    handle.abort();
+}
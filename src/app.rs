@@ -1,40 +1,32 @@
 use crate::{
+    backend::{use_connector, Backend, Connector},
+    config::{use_config, Config, CONFIG_FILE},
     pages,
-    session::{use_session, Session},
+    session::{use_session, use_settings, Session, Settings},
+    utils::error_message,
 };
-use elkato_api::{cors::CorsProxy, Credentials};
-use pages::{index::Index, Pages};
-use patternfly_yew::*;
-use url::Url;
+use elkato_api::Credentials;
+use pages::{create::Create, index::Index, Pages};
+use patternfly_yew::{
+    prelude::{Level, Size},
+    *,
+};
+use std::rc::Rc;
 use yew::prelude::*;
+use yew_hooks::{use_async_with_options, UseAsyncOptions};
 use yew_nested_router::{prelude::*, Switch as RouterSwitch};
 
-pub const FRONTEND_URL: &str = "https://www.elkato.de/buchung/";
-
-#[cfg(not(debug_assertions))]
-pub fn cors_proxy() -> CorsProxy {
-    pub const CORS_API_URL: &str = "https://elkato.dentrassi.de/proxy.php";
-
-    CorsProxy::Query {
-        url: Url::parse(CORS_API_URL).unwrap(),
-        parameter: "url".to_string(),
-    }
-}
-
-#[cfg(debug_assertions)]
-pub fn cors_proxy() -> CorsProxy {
-    pub const CORS_API_URL: &str = "http://localhost:9999";
-
-    CorsProxy::Prepend(Url::parse(CORS_API_URL).unwrap())
-}
-
 #[function_component(Application)]
 pub fn app() -> Html {
     let credentials = use_session();
+    let settings = use_settings();
 
     log::info!("Credentials: {:?}", *credentials);
 
-    let session = Session { credentials };
+    let session = Session {
+        credentials,
+        settings,
+    };
 
     html!(
         <>
@@ -64,8 +56,35 @@ pub struct WithCredentialsProps {
 
 #[function_component(WithCredentials)]
 pub fn with_credentials(props: &WithCredentialsProps) -> Html {
-    let credentials = props.credentials.clone();
     let session = use_context::<Session>().unwrap();
+    let config = use_config();
+    let connector = use_connector();
+
+    let settings = session.settings();
+
+    let backend = use_memo(
+        |(credentials, timezone, demo)| match demo {
+            true => Connector::demo().connect(credentials.clone(), *timezone),
+            false => connector.connect(credentials.clone(), *timezone),
+        },
+        (
+            props.credentials.clone(),
+            settings.timezone(config.timezone),
+            // the demo may have been disabled since logging in
+            settings.demo && config.features.demo,
+        ),
+    );
+
+    let toggle_time = {
+        let session = session.clone();
+        let settings = settings.clone();
+        Callback::from(move |_| {
+            session.set_settings(Settings {
+                browser_time: !settings.browser_time,
+                ..settings.clone()
+            });
+        })
+    };
 
     let logout = {
         Callback::from(move |_| {
@@ -75,28 +94,104 @@ pub fn with_credentials(props: &WithCredentialsProps) -> Html {
 
     let tools = html!(
         <>
+            <Link<Pages> target={Pages::Create}>
+                <Button label="New booking" variant={Variant::Primary} />
+            </Link<Pages>>
+            <Button
+                label={if settings.browser_time { "Local time" } else { "Club time" }}
+                variant={Variant::Plain}
+                onclick={toggle_time}
+            />
             <Button icon={Icon::PowerOff} onclick={logout} />
         </>
     );
 
-    html!(
-        <Page {tools}>
-            <Router<Pages> default={Pages::Index}>
+    let credentials = props.credentials.clone();
+    let content = match &*backend {
+        Ok(backend) => html!(
+            <ContextProvider<Backend> context={backend.clone()}>
                 <RouterSwitch<Pages> render={move |target| match target {
-                    Pages::Index => html!(<Index credentials={credentials.clone()}/>)
+                    Pages::Index => html!(<Index credentials={credentials.clone()}/>),
+                    Pages::Create => html!(<Create/>),
                 }
             }/>
-            </Router<Pages>>
+            </ContextProvider<Backend>>
+        ),
+        Err(err) => html!(
+            <PageSection variant={PageSectionVariant::Light}>
+                { format!("Error: {}", error_message(err)) }
+            </PageSection>
+        ),
+    };
+
+    html!(
+        <Router<Pages> default={Pages::Index}>
+            <Page {tools}>
+                { content }
+            </Page>
+        </Router<Pages>>
+    )
+}
+
+#[derive(Clone, Debug, Properties, PartialEq)]
+pub struct ConfiguredProps {
+    pub config: Rc<Config>,
+}
+
+/// The application, once the configuration is loaded.
+#[function_component(Configured)]
+pub fn configured(props: &ConfiguredProps) -> Html {
+    let connector = use_memo(
+        |config| Connector::elkato(config.clone()),
+        props.config.clone(),
+    );
+
+    html!(
+        <ContextProvider<Rc<Config>> context={props.config.clone()}>
+            <ContextProvider<Connector> context={(*connector).clone()}>
+                <Application/>
+            </ContextProvider<Connector>>
+        </ContextProvider<Rc<Config>>>
+    )
+}
+
+#[derive(Clone, Debug, Properties, PartialEq, Eq)]
+pub struct ConfigErrorProps {
+    pub error: String,
+}
+
+#[function_component(ConfigError)]
+pub fn config_error(props: &ConfigErrorProps) -> Html {
+    html!(
+        <Page>
+            <PageSection variant={PageSectionVariant::Light}>
+                <Title level={Level::H1} size={Size::XXXXLarge}>{ "Invalid configuration" }</Title>
+            </PageSection>
+            <PageSection variant={PageSectionVariant::Light}>
+                <p>{ &props.error }</p>
+                <p>{ format!("Please check the file {CONFIG_FILE}, next to the index.html of the application.") }</p>
+            </PageSection>
         </Page>
     )
 }
 
 #[function_component(Main)]
 pub fn main() -> Html {
+    let config = use_async_with_options(
+        async { Config::load().await.map(Rc::new) },
+        UseAsyncOptions::enable_auto(),
+    );
+
+    let content = match (&config.data, &config.error) {
+        (Some(config), _) => html!(<Configured config={config.clone()}/>),
+        (None, Some(error)) => html!(<ConfigError error={error.clone()}/>),
+        (None, None) => html!(),
+    };
+
     html!(
         <ToastViewer>
             <BackdropViewer>
-                <Application/>
+                { content }
             </BackdropViewer>
         </ToastViewer>
     )
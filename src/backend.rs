@@ -0,0 +1,90 @@
+//! Providing the components with a [`BookingBackend`].
+//!
+//! The application provides a [`Connector`] as context, which creates the backend for the
+//! credentials of the user. Once logged in, the backend itself is provided as context too. This
+//! allows running the frontend against something other than Elkato, by providing a different
+//! connector.
+
+use crate::config::Config;
+use chrono::Utc;
+use chrono_tz::Tz;
+use elkato_api::{backend::BookingBackend, Credentials, Error};
+use std::{ops::Deref, rc::Rc};
+use yew::prelude::*;
+
+/// The backend, as used by the components.
+#[derive(Clone)]
+pub struct Backend(Rc<dyn BookingBackend>);
+
+impl Backend {
+    pub fn new<B: BookingBackend + 'static>(backend: B) -> Self {
+        Self(Rc::new(backend))
+    }
+}
+
+impl Deref for Backend {
+    type Target = dyn BookingBackend;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+impl PartialEq for Backend {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+type Connect = dyn Fn(Credentials, Tz) -> Result<Backend, Error>;
+
+/// Creates the backend for the credentials of a user.
+#[derive(Clone)]
+pub struct Connector(Rc<Connect>);
+
+impl Connector {
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(Credentials, Tz) -> Result<Backend, Error> + 'static,
+    {
+        Self(Rc::new(f))
+    }
+
+    /// Connects to the offline demo, with sample data generated at the time of connecting.
+    pub fn demo() -> Self {
+        Self::new(|credentials, timezone| {
+            Ok(Backend::new(elkato_api::backend::demo(
+                &credentials.username,
+                Utc::now(),
+                timezone,
+            )))
+        })
+    }
+
+    /// Connects to the Elkato instance of the configuration.
+    pub fn elkato(config: Rc<Config>) -> Self {
+        Self::new(move |credentials, timezone| config.api(credentials, timezone).map(Backend::new))
+    }
+
+    pub fn connect(&self, credentials: Credentials, timezone: Tz) -> Result<Backend, Error> {
+        (self.0)(credentials, timezone)
+    }
+}
+
+impl PartialEq for Connector {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Get the backend of the logged in user.
+#[hook]
+pub fn use_backend() -> Backend {
+    use_context::<Backend>().expect("Must be used inside a backend context")
+}
+
+/// Get the connector, creating the backend when logging in.
+#[hook]
+pub fn use_connector() -> Connector {
+    use_context::<Connector>().expect("Must be used inside a connector context")
+}
@@ -0,0 +1,356 @@
+//! The runtime configuration, loaded from `config.json` next to `index.html`.
+//!
+//! This allows hosting several instances of the frontend, using the same build. All fields are
+//! optional, and default to using the public Elkato instance through the hosted proxy, falling
+//! back to the `proxy.php` deployed next to the application.
+
+use chrono_tz::Tz;
+use elkato_api::{cors::CorsProxy, Api, Credentials, DEFAULT_TIMEZONE};
+use reqwest::header::HeaderName;
+use serde::{Deserialize, Deserializer};
+use std::rc::Rc;
+use url::Url;
+use yew::prelude::*;
+
+/// The name of the configuration file, relative to the location of the application.
+pub const CONFIG_FILE: &str = "config.json";
+
+const DEFAULT_ELKATO_URL: &str = "https://www.elkato.de/buchung/";
+const DEFAULT_PROXY_URL: &str = "https://elkato.dentrassi.de/proxy.php";
+/// The proxy shipped with the application, relative to its location.
+const BUNDLED_PROXY_URL: &str = "proxy.php";
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The base URL of the Elkato instance, e.g. pointing to the mock server.
+    pub elkato_url: Url,
+    /// The CORS proxies to use. With more than one, the next one is used when one fails.
+    pub proxies: Vec<Proxy>,
+    /// The default timezone of the club, if the user didn't provide one.
+    #[serde(deserialize_with = "timezone")]
+    pub timezone: Tz,
+    /// The range of bookings shown on the index page.
+    pub window: Window,
+    pub features: Features,
+    /// The location of the configuration, which relative proxy URLs are resolved against.
+    #[serde(skip)]
+    pub base: Option<Url>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            elkato_url: Url::parse(DEFAULT_ELKATO_URL).unwrap(),
+            proxies: [DEFAULT_PROXY_URL, BUNDLED_PROXY_URL]
+                .into_iter()
+                .map(|url| Proxy::Query {
+                    url: url.to_string(),
+                    parameter: default_parameter(),
+                    authorization_header: None,
+                })
+                .collect(),
+            timezone: DEFAULT_TIMEZONE,
+            window: Default::default(),
+            features: Default::default(),
+            base: None,
+        }
+    }
+}
+
+/// A CORS proxy, see [`CorsProxy`].
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Proxy {
+    /// Access Elkato directly.
+    None,
+    /// Append the target URL to the URL of the proxy.
+    Prepend {
+        /// The URL of the proxy, which may be relative to the configuration.
+        url: String,
+        /// Send the credentials in this header, instead of the `Authorization` header.
+        #[serde(default, deserialize_with = "header_name")]
+        authorization_header: Option<HeaderName>,
+    },
+    /// Pass the target URL in a query parameter.
+    Query {
+        /// The URL of the proxy, which may be relative to the configuration.
+        url: String,
+        #[serde(default = "default_parameter")]
+        parameter: String,
+        /// Send the credentials in this header, instead of the `Authorization` header.
+        #[serde(default, deserialize_with = "header_name")]
+        authorization_header: Option<HeaderName>,
+    },
+}
+
+impl Proxy {
+    fn to_cors_proxy(&self, base: Option<&Url>) -> Result<CorsProxy, String> {
+        let resolve = |url: &str| {
+            match base {
+                Some(base) => base.join(url),
+                None => Url::parse(url),
+            }
+            .map_err(|err| format!("Invalid proxy URL '{url}': {err}"))
+        };
+
+        let (proxy, header) = match self {
+            Self::None => return Ok(CorsProxy::None),
+            Self::Prepend {
+                url,
+                authorization_header,
+            } => (CorsProxy::Prepend(resolve(url)?), authorization_header),
+            Self::Query {
+                url,
+                parameter,
+                authorization_header,
+            } => (
+                CorsProxy::Query {
+                    url: resolve(url)?,
+                    parameter: parameter.clone(),
+                },
+                authorization_header,
+            ),
+        };
+
+        Ok(match header {
+            Some(header) => CorsProxy::AuthorizationHeader {
+                proxy: Box::new(proxy),
+                header: header.clone(),
+            },
+            None => proxy,
+        })
+    }
+}
+
+/// The number of days before and after today.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Window {
+    pub past_days: u32,
+    pub future_days: u32,
+}
+
+impl Default for Window {
+    fn default() -> Self {
+        Self {
+            past_days: 7,
+            future_days: 7,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Features {
+    /// Offer the offline demo on the login page.
+    pub demo: bool,
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Self { demo: true }
+    }
+}
+
+fn default_parameter() -> String {
+    "url".to_string()
+}
+
+fn timezone<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Tz, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    name.parse()
+        .map_err(|_| serde::de::Error::custom(format!("unknown timezone: {name}")))
+}
+
+fn header_name<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<HeaderName>, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    name.parse()
+        .map(Some)
+        .map_err(|_| serde::de::Error::custom(format!("invalid header name: {name}")))
+}
+
+impl Config {
+    pub fn cors_proxy(&self) -> Result<CorsProxy, String> {
+        let mut proxies = self
+            .proxies
+            .iter()
+            .map(|proxy| proxy.to_cors_proxy(self.base.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(match proxies.len() {
+            0 => CorsProxy::None,
+            1 => proxies.remove(0),
+            _ => CorsProxy::fallback(proxies),
+        })
+    }
+
+    pub fn api(&self, credentials: Credentials, timezone: Tz) -> Result<Api, elkato_api::Error> {
+        let proxy = self
+            .cors_proxy()
+            .map_err(elkato_api::Error::InvalidRequest)?;
+        Ok(Api::new(self.elkato_url.clone(), proxy, credentials)?.with_timezone(timezone))
+    }
+
+    /// Load the configuration, located next to the application.
+    pub async fn load() -> Result<Self, String> {
+        let base = gloo_utils::window()
+            .location()
+            .href()
+            .map_err(|err| format!("Unable to get the location: {err:?}"))?;
+        let url = Url::parse(&base)
+            .and_then(|base| base.join(CONFIG_FILE))
+            .map_err(|err| format!("Invalid location: {err}"))?;
+
+        log::info!("Loading configuration: {url}");
+
+        let response = reqwest::get(url.clone())
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| format!("Unable to load {CONFIG_FILE}: {err}"))?;
+        let text = response
+            .text()
+            .await
+            .map_err(|err| format!("Unable to load {CONFIG_FILE}: {err}"))?;
+
+        Self::parse(&text, url)
+    }
+
+    /// Parse the configuration, located at `base`.
+    pub fn parse(text: &str, base: Url) -> Result<Self, String> {
+        let mut config: Self =
+            serde_json::from_str(text).map_err(|err| format!("Invalid {CONFIG_FILE}: {err}"))?;
+        config.base = Some(base);
+
+        if config.elkato_url.cannot_be_a_base() || !config.elkato_url.path().ends_with('/') {
+            return Err(format!(
+                "Invalid {CONFIG_FILE}: the Elkato URL must end with a slash: {}",
+                config.elkato_url
+            ));
+        }
+
+        config
+            .cors_proxy()
+            .map_err(|err| format!("Invalid {CONFIG_FILE}: {err}"))?;
+
+        Ok(config)
+    }
+}
+
+/// Get the configuration of the application.
+#[hook]
+pub fn use_config() -> Rc<Config> {
+    use_context::<Rc<Config>>().expect("Must be used inside a config context")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn base() -> Url {
+        Url::parse("https://example.com/app/config.json").unwrap()
+    }
+
+    #[test]
+    fn defaults() {
+        let config = Config::parse("{}", base()).unwrap();
+        assert_eq!(
+            config,
+            Config {
+                base: Some(base()),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn valid() {
+        let config = Config::parse(
+            r#"{
+                "elkato_url": "http://localhost:8081/buchung/",
+                "proxies": [
+                    { "prepend": { "url": "https://proxy.example.com/", "authorization_header": "X-Proxy-Authorization" } },
+                    { "query": { "url": "https://other.example.com/proxy.php", "parameter": "target" } },
+                    "none"
+                ],
+                "timezone": "Europe/Vienna",
+                "window": { "past_days": 1, "future_days": 30 }
+            }"#,
+            base(),
+        )
+        .unwrap();
+
+        assert_eq!(config.elkato_url.as_str(), "http://localhost:8081/buchung/");
+        assert_eq!(config.timezone, chrono_tz::Europe::Vienna);
+        assert_eq!(
+            config.window,
+            Window {
+                past_days: 1,
+                future_days: 30
+            }
+        );
+
+        let proxy = config.cors_proxy().unwrap();
+        let CorsProxy::Fallback(fallback) = proxy else {
+            panic!("Expected a fallback: {proxy:?}");
+        };
+        match fallback.proxies() {
+            [CorsProxy::AuthorizationHeader { proxy, header }, CorsProxy::Query { url, parameter }, CorsProxy::None] =>
+            {
+                assert!(
+                    matches!(&**proxy, CorsProxy::Prepend(url) if url.as_str() == "https://proxy.example.com/")
+                );
+                assert_eq!(header.as_str(), "x-proxy-authorization");
+                assert_eq!(url.as_str(), "https://other.example.com/proxy.php");
+                assert_eq!(parameter, "target");
+            }
+            proxies => panic!("Unexpected proxies: {proxies:?}"),
+        }
+    }
+
+    #[test]
+    fn relative_proxy() {
+        let config = Config::parse(
+            r#"{ "proxies": [ { "query": { "url": "proxy.php" } }, { "prepend": { "url": "/cors/" } } ] }"#,
+            base(),
+        )
+        .unwrap();
+
+        let proxy = config.cors_proxy().unwrap();
+        let CorsProxy::Fallback(fallback) = proxy else {
+            panic!("Expected a fallback: {proxy:?}");
+        };
+        match fallback.proxies() {
+            [CorsProxy::Query { url, parameter }, CorsProxy::Prepend(prepend)] => {
+                assert_eq!(url.as_str(), "https://example.com/app/proxy.php");
+                assert_eq!(parameter, "url");
+                assert_eq!(prepend.as_str(), "https://example.com/cors/");
+            }
+            proxies => panic!("Unexpected proxies: {proxies:?}"),
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        for (json, expected) in [
+            (
+                r#"{ "proxies": [ { "prepend": { "url": "https://[proxy" } } ] }"#,
+                "Invalid proxy URL",
+            ),
+            (
+                r#"{ "proxies": [ { "query": { "url": "proxy.php", "authorization_header": "not a header" } } ] }"#,
+                "invalid header name",
+            ),
+            (r#"{ "proxies": [ "direct" ] }"#, "unknown variant"),
+            (
+                r#"{ "elkato_url": "https://www.elkato.de/buchung" }"#,
+                "must end with a slash",
+            ),
+            (r#"{ "timezone": "Europe/Nowhere" }"#, "unknown timezone"),
+            (r#"{ "proxy": "none" }"#, "unknown field"),
+        ] {
+            let err = Config::parse(json, base()).unwrap_err();
+            assert!(err.contains(expected), "{json}: {err}");
+        }
+    }
+}
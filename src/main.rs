@@ -1,6 +1,8 @@
 #![recursion_limit = "1024"]
 
 mod app;
+mod backend;
+mod config;
 mod pages;
 mod session;
 
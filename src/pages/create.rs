@@ -0,0 +1,239 @@
+use crate::{
+    backend::use_backend,
+    config::use_config,
+    session::Session,
+    utils::{error_message, format_date, DisplayZone},
+};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use elkato_api::{
+    availability::{Conflict, Interval},
+    NewBooking,
+};
+use patternfly_yew::prelude::*;
+use yew::prelude::*;
+use yew_hooks::{use_async, use_async_with_options, UseAsyncOptions};
+
+/// The format of a `datetime-local` input field.
+const INPUT_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+fn parse_input(value: &str, zone: DisplayZone) -> Option<DateTime<Utc>> {
+    zone.to_utc(&NaiveDateTime::parse_from_str(value, INPUT_FORMAT).ok()?)
+}
+
+fn format_interval(interval: &Interval, now: &DateTime<Utc>, zone: DisplayZone) -> String {
+    match zone {
+        DisplayZone::Club(tz) => format_interval_in(interval, now, &tz),
+        DisplayZone::Browser => format_interval_in(interval, now, &Local),
+    }
+}
+
+fn format_interval_in<Tz>(interval: &Interval, now: &DateTime<Utc>, tz: &Tz) -> String
+where
+    Tz: TimeZone,
+    Tz::Offset: std::fmt::Display,
+{
+    let start = interval.start.with_timezone(tz);
+    let end = interval.end.with_timezone(tz);
+
+    format!(
+        "{} {} → {} {}",
+        format_date(&start.date_naive(), now, tz),
+        start.format("%H:%M"),
+        format_date(&end.date_naive(), now, tz),
+        end.format("%H:%M"),
+    )
+}
+
+#[function_component(Create)]
+pub fn create() -> Html {
+    let settings = use_context::<Session>().unwrap().settings();
+    let zone = settings.display_zone(use_config().timezone);
+    let backend = use_backend();
+
+    let resources = {
+        let backend = backend.clone();
+        use_async_with_options(
+            async move {
+                backend
+                    .list_resources()
+                    .await
+                    .map_err(|err| error_message(&err))
+            },
+            UseAsyncOptions::enable_auto(),
+        )
+    };
+
+    let resource = use_state_eq(String::new);
+    let start = use_state_eq(String::new);
+    let end = use_state_eq(String::new);
+    let description = use_state_eq(String::new);
+
+    let interval = match (parse_input(&start, zone), parse_input(&end, zone)) {
+        (Some(start), Some(end)) if start < end => Some(Interval::new(start, end)),
+        _ => None,
+    };
+    let proposed = match (resource.is_empty(), interval) {
+        (false, Some(interval)) => Some(((*resource).clone(), interval)),
+        _ => None,
+    };
+
+    // check for conflicts, whenever the proposed booking changes
+
+    let conflicts = {
+        let backend = backend.clone();
+        let proposed = proposed.clone();
+        use_async(async move {
+            match proposed {
+                Some((resource, interval)) => backend
+                    .check_conflicts(&resource, interval)
+                    .await
+                    .map_err(|err| error_message(&err)),
+                None => Ok(vec![]),
+            }
+        })
+    };
+
+    {
+        let conflicts = conflicts.clone();
+        use_effect_with_deps(move |_| conflicts.run(), proposed.clone());
+    }
+
+    let created = {
+        let proposed = proposed.clone();
+        let description = (*description).clone();
+        use_async(async move {
+            let (resource, interval) = proposed.ok_or_else(|| "Incomplete booking".to_string())?;
+            backend
+                .create_booking(NewBooking {
+                    resource,
+                    start: interval.start,
+                    end: interval.end,
+                    description,
+                    owner: None,
+                })
+                .await
+                .map_err(|err| error_message(&err))
+        })
+    };
+
+    let onsubmit = {
+        let created = created.clone();
+        Callback::from(move |_| created.run())
+    };
+
+    let set_resource = {
+        let resource = resource.clone();
+        Callback::from(move |e: Event| {
+            resource.set(
+                e.target_unchecked_into::<web_sys::HtmlSelectElement>()
+                    .value(),
+            )
+        })
+    };
+    let set_start = {
+        let start = start.clone();
+        Callback::from(move |s| start.set(s))
+    };
+    let set_end = {
+        let end = end.clone();
+        Callback::from(move |s| end.set(s))
+    };
+    let set_description = {
+        let description = description.clone();
+        Callback::from(move |s| description.set(s))
+    };
+
+    let now = Utc::now();
+
+    html!(<>
+        <PageSection variant={PageSectionVariant::Light} sticky={[PageSectionSticky::Top]}>
+            <Title level={Level::H1} size={Size::XXXXLarge}>{ "New booking" }</Title>
+        </PageSection>
+        <PageSection variant={PageSectionVariant::Light}>
+            <Form {onsubmit}>
+                <FormGroup label="Resource">
+                    <select class="pf-c-form-control" required=true name="resource" onchange={set_resource}>
+                        <option value="" selected={resource.is_empty()}>{ "Select a resource" }</option>
+                        { for resources.data.iter().flatten().map(|r| {
+                            let label = match &r.group {
+                                Some(group) => format!("{} ({})", r.name, group),
+                                None => r.name.clone(),
+                            };
+                            html!(<option value={r.id.clone()} selected={*resource == r.id}>{ label }</option>)
+                        })}
+                    </select>
+                </FormGroup>
+                <FormGroup label="Start">
+                    <TextInput required=true name="start" r#type="datetime-local" onchange={set_start}/>
+                </FormGroup>
+                <FormGroup label="End">
+                    <TextInput required=true name="end" r#type="datetime-local" onchange={set_end}/>
+                </FormGroup>
+                <FormGroup label="Description">
+                    <TextInput name="description" onchange={set_description}/>
+                </FormGroup>
+
+                if let Some(error) = &resources.error {
+                    <div class="pf-c-alert pf-m-danger pf-m-inline">
+                        <p class="pf-c-alert__title">{ format!("Failed to load resources: {error}") }</p>
+                    </div>
+                }
+                if let (Some(start), Some(end)) = (parse_input(&start, zone), parse_input(&end, zone)) {
+                    if start >= end {
+                        <div class="pf-c-alert pf-m-warning pf-m-inline">
+                            <p class="pf-c-alert__title">{ "The end must be after the start" }</p>
+                        </div>
+                    }
+                }
+                { conflicts_alert(&conflicts.data, &now, zone) }
+                if let Some(error) = &conflicts.error {
+                    <div class="pf-c-alert pf-m-warning pf-m-inline">
+                        <p class="pf-c-alert__title">{ format!("Failed to check for conflicts: {error}") }</p>
+                    </div>
+                }
+
+                if let Some(booking) = &created.data {
+                    <div class="pf-c-alert pf-m-success pf-m-inline">
+                        <p class="pf-c-alert__title">{ format!("Booking {} created", booking.id) }</p>
+                    </div>
+                }
+                if let Some(error) = &created.error {
+                    <div class="pf-c-alert pf-m-danger pf-m-inline">
+                        <p class="pf-c-alert__title">{ format!("Failed to create booking: {error}") }</p>
+                    </div>
+                }
+
+                <ActionGroup>
+                    <Button
+                        label="Book"
+                        r#type={ButtonType::Submit}
+                        variant={Variant::Primary}
+                        disabled={proposed.is_none() || created.loading}
+                    />
+                </ActionGroup>
+            </Form>
+        </PageSection>
+    </>)
+}
+
+fn conflicts_alert(
+    conflicts: &Option<Vec<Conflict>>,
+    now: &DateTime<Utc>,
+    zone: DisplayZone,
+) -> Html {
+    match conflicts {
+        Some(conflicts) if !conflicts.is_empty() => html!(
+            <div class="pf-c-alert pf-m-warning pf-m-inline">
+                <p class="pf-c-alert__title">{ "The resource is already booked" }</p>
+                <div class="pf-c-alert__description">
+                    <ul>
+                        { for conflicts.iter().map(|c| html!(
+                            <li>{ format!("{}: {}", c.booking.owner, format_interval(&c.overlap, now, zone)) }</li>
+                        ))}
+                    </ul>
+                </div>
+            </div>
+        ),
+        _ => html!(),
+    }
+}
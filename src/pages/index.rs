@@ -1,36 +1,32 @@
-use crate::utils::format_date;
-use chrono::{DateTime, Duration, Utc};
-use chrono_tz::Europe::Berlin;
+use crate::{
+    backend::{use_backend, Backend},
+    config::{use_config, Window},
+    session::Session,
+    utils::{error_message, format_date, DisplayZone},
+};
+use chrono::{DateTime, Duration, Local, TimeZone, Utc};
 use elkato_api::*;
-use futures::{StreamExt, TryStreamExt};
 use patternfly_yew::prelude::*;
-use url::Url;
 use yew::prelude::*;
 use yew_hooks::{use_async_with_options, UseAsyncOptions};
 
-async fn bookings(credentials: Credentials) -> anyhow::Result<Vec<Booking>> {
+async fn bookings(
+    backend: Backend,
+    owner: String,
+    window: Window,
+) -> Result<Vec<Booking>, elkato_api::Error> {
     log::info!("Load bookings");
 
-    let owner = credentials.username.clone();
-    let api = Api::new(
-        Url::parse(crate::app::FRONTEND_URL)?,
-        crate::app::cors_proxy(),
-        credentials,
-    )?;
-
     let today = Utc::now().date_naive();
 
-    log::info!("Load bookings (begin)");
-
-    api.list_bookings(ListOptions {
-        owner: Some(owner),
-        start_from: Some(today - Duration::days(7)),
-        end_to: Some(today + Duration::days(7)),
-        ..Default::default()
-    })
-    .boxed_local()
-    .try_collect()
-    .await
+    backend
+        .list_bookings(ListOptions {
+            owner: Some(owner),
+            start_from: Some(today - Duration::days(window.past_days.into())),
+            end_to: Some(today + Duration::days(window.future_days.into())),
+            ..Default::default()
+        })
+        .await
 }
 
 fn select(mut bookings: Vec<Booking>) -> Vec<Booking> {
@@ -63,35 +59,46 @@ pub struct Props {
 
 #[function_component(Index)]
 pub fn index(props: &Props) -> Html {
-    let credentials = props.credentials.clone();
-    let bookings = use_async_with_options(
-        async move {
-            {
-                let bookings = bookings(credentials).await;
+    let owner = props.credentials.username.clone();
+    let settings = use_context::<Session>().unwrap().settings();
+    let config = use_config();
+    let backend = use_backend();
+    let bookings = {
+        let backend = backend.clone();
+        let window = config.window.clone();
+        use_async_with_options(
+            async move {
+                {
+                    let bookings = bookings(backend, owner, window).await;
 
-                log::info!("Load bookings (done): {bookings:?}");
+                    log::info!("Load bookings (done): {bookings:?}");
 
-                bookings.map_err(|err| err.to_string()).map(select)
-            }
-        },
-        UseAsyncOptions::enable_auto(),
-    );
+                    bookings.map_err(|err| error_message(&err)).map(select)
+                }
+            },
+            UseAsyncOptions::enable_auto(),
+        )
+    };
 
     html!(<>
         <PageSection variant={PageSectionVariant::Light} sticky={[PageSectionSticky::Top]}>
             <Title level={Level::H1} size={Size::XXXXLarge}>{ "Bookings" }</Title>
+            // the proxy in use is only known once the bookings are loaded
+            if let (false, Some(connection)) = (bookings.loading, backend.connection()) {
+                <small>{ format!("Connected to {connection}") }</small>
+            }
         </PageSection>
         <PageSection variant={PageSectionVariant::Light}> {
             match (bookings.loading, &bookings.data, &bookings.error) {
                 (true, _, _) => html!({ "Loading, ..." }),
                 (false, Some(bookings), _) => html!(
                     <>
-                        <Bookings bookings={bookings.clone()}/>
+                        <Bookings bookings={bookings.clone()} zone={settings.display_zone(config.timezone)}/>
                     </>
                 ),
                 (false, _, Some(error)) => html!(
                     <>
-                        {format!("Error (X): {error}")}
+                        {format!("Error: {error}")}
                     </>
                 ),
                 _ => html!(),
@@ -103,6 +110,7 @@ pub fn index(props: &Props) -> Html {
 #[derive(Properties, PartialEq, Eq)]
 pub struct BookingProps {
     pub bookings: Vec<Booking>,
+    pub zone: DisplayZone,
 }
 
 #[function_component(Bookings)]
@@ -112,7 +120,7 @@ fn bookings(props: &BookingProps) -> Html {
             <Gallery gutter=true>
                 { for props.bookings.iter().cloned().map(|booking| {
                     html!(
-                        <BookingCard {booking} />
+                        <BookingCard {booking} zone={props.zone} />
                     )
                 })}
             </Gallery>
@@ -123,6 +131,7 @@ fn bookings(props: &BookingProps) -> Html {
 #[derive(Properties, PartialEq, Eq)]
 pub struct BookingCardProps {
     pub booking: Booking,
+    pub zone: DisplayZone,
 }
 
 #[function_component(BookingCard)]
@@ -140,10 +149,13 @@ fn booking_card(props: &BookingCardProps) -> Html {
             onclick={make_onclick(&props.booking)}
             {selection}
             title={html!{<>
-                { title(&props.booking, &now) }
+                { title(&props.booking, &now, props.zone) }
             </>}}
             >
             <div>{ &props.booking.resource }</div>
+            if props.booking.booker != props.booking.owner {
+                <div>{ format!("Booked by {} for {}", props.booking.booker, props.booking.owner) }</div>
+            }
             if !props.booking.description.is_empty() {
                 <div>{ &props.booking.description }</div>
             }
@@ -164,13 +176,22 @@ fn make_onclick<E>(sel_booking: &Booking) -> Callback<E> {
     })
 }
 
-fn title(booking: &Booking, now: &DateTime<Utc>) -> String {
+fn title(booking: &Booking, now: &DateTime<Utc>, zone: DisplayZone) -> String {
+    match zone {
+        DisplayZone::Club(tz) => title_in(booking, now, &tz),
+        DisplayZone::Browser => title_in(booking, now, &Local),
+    }
+}
+
+fn title_in<Tz>(booking: &Booking, now: &DateTime<Utc>, tz: &Tz) -> String
+where
+    Tz: TimeZone,
+    Tz::Offset: std::fmt::Display,
+{
     let dur = booking.end - booking.start;
 
     let dur = format_duration(&dur);
 
-    let tz = &Berlin;
-
     let start_date = booking.start.with_timezone(tz).date_naive();
     let end_date = booking.end.with_timezone(tz).date_naive();
 
@@ -213,3 +234,84 @@ fn format_duration(duration: &Duration) -> String {
         }
     }
 }
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod test {
+    use super::*;
+    use crate::{
+        config::Config,
+        session::{use_session, use_settings},
+    };
+    use std::rc::Rc;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[derive(Properties, PartialEq)]
+    struct HarnessProps {
+        backend: Backend,
+        credentials: Credentials,
+    }
+
+    /// Provides the contexts the application provides to the index page.
+    #[function_component(Harness)]
+    fn harness(props: &HarnessProps) -> Html {
+        let session = Session {
+            credentials: use_session(),
+            settings: use_settings(),
+        };
+
+        html!(
+            <ContextProvider<Rc<Config>> context={Rc::new(Config::default())}>
+                <ContextProvider<Session> context={session}>
+                    <ContextProvider<Backend> context={props.backend.clone()}>
+                        <Index credentials={props.credentials.clone()}/>
+                    </ContextProvider<Backend>>
+                </ContextProvider<Session>>
+            </ContextProvider<Rc<Config>>>
+        )
+    }
+
+    #[wasm_bindgen_test]
+    async fn demo_bookings() {
+        let credentials = Credentials {
+            username: "demo".into(),
+            password: "demo".into(),
+            club: "demo".into(),
+        };
+        let backend = Backend::new(elkato_api::backend::demo(
+            &credentials.username,
+            Utc::now(),
+            chrono_tz::Europe::Berlin,
+        ));
+
+        let root = gloo_utils::document().create_element("div").unwrap();
+        gloo_utils::body().append_child(&root).unwrap();
+        yew::Renderer::<Harness>::with_root_and_props(
+            root.clone(),
+            HarnessProps {
+                backend,
+                credentials,
+            },
+        )
+        .render();
+
+        // the bookings get loaded asynchronously
+        let mut text = String::new();
+        for _ in 0..100 {
+            yew::platform::time::sleep(std::time::Duration::from_millis(10)).await;
+            text = root.text_content().unwrap_or_default();
+            if !text.contains("Loading") {
+                break;
+            }
+        }
+
+        assert!(!text.contains("Error"), "{text}");
+        // the current and upcoming bookings of the user
+        assert!(text.contains("Arzttermin"), "{text}");
+        assert!(text.contains("Umzug"), "{text}");
+        // but not the older ones, or the ones of others
+        assert!(!text.contains("Einkauf"), "{text}");
+        assert!(!text.contains("Dienstreise"), "{text}");
+    }
+}
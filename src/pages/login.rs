@@ -1,7 +1,14 @@
-use crate::session::Session;
+use crate::{
+    backend::use_connector,
+    config::use_config,
+    session::{Session, Settings},
+    utils::login_error_message,
+};
+use chrono_tz::Tz;
 use elkato_api::Credentials;
 use patternfly_yew::*;
 use yew::prelude::*;
+use yew_hooks::use_async;
 
 #[function_component(Login)]
 pub fn login() -> Html {
@@ -10,26 +17,61 @@ pub fn login() -> Html {
     let club = use_state_eq(|| String::new());
     let username = use_state_eq(|| String::new());
     let password = use_state_eq(|| String::new());
+    let timezone = use_state_eq(|| String::new());
 
     let session = use_context::<Session>().unwrap();
+    let config = use_config();
+    let connector = use_connector();
 
-    let onsubmit = {
+    // verify the credentials before storing them
+
+    let verify = {
         let session = session.clone();
-        let username = username.clone();
-        let password = password.clone();
-        let club = club.clone();
-        Callback::from(move |_| {
-            session.login(Credentials {
-                username: (*username).clone(),
-                password: (*password).clone(),
-                club: (*club).clone(),
+        let credentials = Credentials {
+            username: (*username).clone(),
+            password: (*password).clone(),
+            club: (*club).clone(),
+        };
+        let timezone = (*timezone).trim().to_string();
+        let default_timezone = config.timezone;
+        use_async(async move {
+            let tz = match timezone.is_empty() {
+                true => default_timezone,
+                false => timezone
+                    .parse::<Tz>()
+                    .map_err(|_| format!("Unknown timezone: {timezone}"))?,
+            };
+
+            connector
+                .connect(credentials.clone(), tz)
+                .map_err(|err| login_error_message(&err))?
+                .verify_credentials()
+                .await
+                .map_err(|err| login_error_message(&err))?;
+
+            session.set_settings(Settings {
+                timezone: (!timezone.is_empty()).then_some(timezone),
+                demo: false,
+                ..session.settings()
             });
+            session.login(credentials);
+            Ok::<_, String>(())
         })
     };
 
+    let onsubmit = {
+        let verify = verify.clone();
+        Callback::from(move |_| verify.run())
+    };
+
+    // the demo runs offline, with generated data
     let onclick_demo = {
         let session = session.clone();
         Callback::from(move |_| {
+            session.set_settings(Settings {
+                demo: true,
+                ..session.settings()
+            });
             session.login(Credentials {
                 username: "demo".to_string(),
                 password: "demo".to_string(),
@@ -46,6 +88,10 @@ pub fn login() -> Html {
         let username = username.clone();
         Callback::from(move |s| username.set(s))
     };
+    let set_timezone = {
+        let timezone = timezone.clone();
+        Callback::from(move |s| timezone.set(s))
+    };
     let set_password = {
         let password = password.clone();
         Callback::from(move |s| password.set(s))
@@ -67,9 +113,19 @@ pub fn login() -> Html {
                         <FormGroup label="Password">
                             <TextInput required=true name="password" r#type="password" onchange={set_password}/>
                         </FormGroup>
+                        <FormGroup label="Timezone">
+                            <TextInput name="timezone" placeholder={config.timezone.name()} onchange={set_timezone}/>
+                        </FormGroup>
+                        if let Some(error) = &verify.error {
+                            <div class="pf-c-alert pf-m-danger pf-m-inline">
+                                <p class="pf-c-alert__title">{ error }</p>
+                            </div>
+                        }
                         <ActionGroup>
-                            <Button label="Log In" r#type={ButtonType::Submit} variant={Variant::Primary}/>
-                            <Button label="Use Demo" r#type={ButtonType::Button} variant={Variant::Secondary} onclick={onclick_demo}/>
+                            <Button label="Log In" r#type={ButtonType::Submit} variant={Variant::Primary} disabled={verify.loading}/>
+                            if config.features.demo {
+                                <Button label="Use Demo" r#type={ButtonType::Button} variant={Variant::Secondary} onclick={onclick_demo}/>
+                            }
                         </ActionGroup>
                     </Form>
                 </LoginMainBody>
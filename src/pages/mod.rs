@@ -1,3 +1,4 @@
+pub mod create;
 pub mod index;
 pub mod login;
 
@@ -7,4 +8,5 @@ use yew_nested_router::Target;
 pub enum Pages {
     #[target(index)]
     Index,
+    Create,
 }
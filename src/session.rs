@@ -1,12 +1,47 @@
+use crate::utils::DisplayZone;
+use chrono_tz::Tz;
 use elkato_api::Credentials;
+use serde::{Deserialize, Serialize};
 use yew::prelude::*;
 use yew_hooks::{use_local_storage, UseLocalStorageHandle};
 
 const KEY_CREDENTIALS: &str = "credentials";
+const KEY_SETTINGS: &str = "settings";
+
+/// Settings of the user, stored alongside the credentials.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Settings {
+    /// The timezone of the club, defaults to the timezone of the configuration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// Show times in the timezone of the browser, instead of the club's timezone.
+    #[serde(default)]
+    pub browser_time: bool,
+    /// Use the offline demo, instead of Elkato.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub demo: bool,
+}
+
+impl Settings {
+    pub fn timezone(&self, default: Tz) -> Tz {
+        self.timezone
+            .as_deref()
+            .and_then(|tz| tz.parse().ok())
+            .unwrap_or(default)
+    }
+
+    pub fn display_zone(&self, default: Tz) -> DisplayZone {
+        match self.browser_time {
+            true => DisplayZone::Browser,
+            false => DisplayZone::Club(self.timezone(default)),
+        }
+    }
+}
 
 #[derive(Clone, PartialEq)]
 pub struct Session {
     pub credentials: UseLocalStorageHandle<Credentials>,
+    pub settings: UseLocalStorageHandle<Settings>,
 }
 
 impl Session {
@@ -16,6 +51,18 @@ impl Session {
 
     pub fn logout(&self) {
         self.credentials.delete();
+        self.set_settings(Settings {
+            demo: false,
+            ..self.settings()
+        });
+    }
+
+    pub fn settings(&self) -> Settings {
+        (*self.settings).clone().unwrap_or_default()
+    }
+
+    pub fn set_settings(&self, settings: Settings) {
+        self.settings.set(settings);
     }
 }
 
@@ -23,3 +70,8 @@ impl Session {
 pub fn use_session() -> UseLocalStorageHandle<Credentials> {
     use_local_storage::<Credentials>(KEY_CREDENTIALS.into())
 }
+
+#[hook]
+pub fn use_settings() -> UseLocalStorageHandle<Settings> {
+    use_local_storage::<Settings>(KEY_SETTINGS.into())
+}
@@ -1,4 +1,30 @@
-use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use elkato_api::Error;
+
+/// The timezone to show, and enter, dates and times in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DisplayZone {
+    /// The timezone of the club.
+    Club(chrono_tz::Tz),
+    /// The timezone of the browser.
+    Browser,
+}
+
+impl DisplayZone {
+    /// Convert a date and time, entered by the user, to UTC.
+    pub fn to_utc(&self, datetime: &NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Club(tz) => datetime
+                .and_local_timezone(*tz)
+                .earliest()
+                .map(|dt| dt.with_timezone(&Utc)),
+            Self::Browser => datetime
+                .and_local_timezone(Local)
+                .earliest()
+                .map(|dt| dt.with_timezone(&Utc)),
+        }
+    }
+}
 
 pub fn format_date<Tz>(date: &NaiveDate, now: &DateTime<Utc>, tz: &Tz) -> String
 where
@@ -18,3 +44,30 @@ where
         date.format("%v").to_string()
     }
 }
+
+/// Create a message for the user, explaining what went wrong.
+pub fn error_message(err: &Error) -> String {
+    match err {
+        Error::Unauthorized => {
+            "Elkato rejected the credentials. Please log out and log in again.".to_string()
+        }
+        Error::UnknownClub => {
+            "Elkato doesn't know this club. Please check the club name.".to_string()
+        }
+        Error::ProxyFailure(_) => format!("Unable to reach Elkato, please try again later: {err}"),
+        Error::ParseError { .. } => format!(
+            "Unable to understand the response from Elkato, maybe its layout changed: {err}"
+        ),
+        err => err.to_string(),
+    }
+}
+
+/// Create a message for the login page, where logging in again is what the user just did.
+pub fn login_error_message(err: &Error) -> String {
+    match err {
+        Error::Unauthorized => {
+            "Elkato rejected the username or password. Please check them and try again.".to_string()
+        }
+        err => error_message(err),
+    }
+}